@@ -0,0 +1,339 @@
+use crate::*;
+use cosmwasm_std::{Addr, Binary, Decimal, Deps, DepsMut, Empty, Env, MessageInfo};
+use cw2::ContractVersion;
+use cw_multi_test::{no_init, BasicApp, BasicAppBuilder, Contract, ContractWrapper, Executor};
+
+type TorramApp = BasicApp<Empty, TorramQuery>;
+
+fn mock_app() -> TorramApp {
+    BasicAppBuilder::<Empty, TorramQuery>::new_custom().build(no_init)
+}
+
+fn price_contract() -> Box<dyn Contract<Empty, TorramQuery>> {
+    Box::new(
+        ContractWrapper::new(execute, instantiate, query)
+            .with_migrate(migrate)
+            .with_reply(reply),
+    )
+}
+
+fn instantiate_price(app: &mut TorramApp, owner: &Addr, assets: Vec<AssetInfo>) -> Addr {
+    let code_id = app.store_code(price_contract());
+    let msg = InstantiateMsg {
+        admin: owner.to_string(),
+        assets,
+        min_reporters: None,
+        history_retention: None,
+        max_age: vec![],
+        feeders: None,
+        quorum: None,
+        pausers: None,
+        basket: None,
+        depeg_threshold: None,
+    };
+    app.instantiate_contract(
+        code_id,
+        owner.clone(),
+        &msg,
+        &[],
+        "price",
+        Some(owner.to_string()),
+    )
+    .unwrap()
+}
+
+const RECEIVED: Item<Vec<PriceResponse>> = Item::new("received");
+
+/// Subscriber that keeps the last pushed prices, or rejects every callback
+/// when instantiated with `true`.
+fn consumer_contract() -> Box<dyn Contract<Empty, TorramQuery>> {
+    const FAIL: Item<bool> = Item::new("fail");
+    fn instantiate(
+        deps: DepsMut<TorramQuery>,
+        _env: Env,
+        _info: MessageInfo,
+        fail: bool,
+    ) -> StdResult<Response> {
+        FAIL.save(deps.storage, &fail)?;
+        RECEIVED.save(deps.storage, &vec![])?;
+        Ok(Response::new())
+    }
+    fn execute(
+        deps: DepsMut<TorramQuery>,
+        _env: Env,
+        _info: MessageInfo,
+        msg: SubscriberMsg,
+    ) -> StdResult<Response> {
+        if FAIL.load(deps.storage)? {
+            return Err(StdError::generic_err("consumer is broken"));
+        }
+        let SubscriberMsg::OraclePriceUpdate { prices } = msg;
+        RECEIVED.save(deps.storage, &prices)?;
+        Ok(Response::new())
+    }
+    fn query(deps: Deps<TorramQuery>, _env: Env, _msg: Empty) -> StdResult<Binary> {
+        to_json_binary(&RECEIVED.load(deps.storage)?)
+    }
+    Box::new(ContractWrapper::new(execute, instantiate, query))
+}
+
+/// Stand-in for releases that kept every price in `LEGACY_PRICES` and did not
+/// record a cw2 version.
+fn legacy_contract() -> Box<dyn Contract<Empty, TorramQuery>> {
+    fn instantiate(
+        deps: DepsMut<TorramQuery>,
+        _env: Env,
+        info: MessageInfo,
+        msg: LegacyPriceData,
+    ) -> StdResult<Response> {
+        ADMIN.save(deps.storage, &info.sender)?;
+        LEGACY_PRICES.save(deps.storage, &msg)?;
+        Ok(Response::new())
+    }
+    fn execute(
+        _deps: DepsMut<TorramQuery>,
+        _env: Env,
+        _info: MessageInfo,
+        _msg: Empty,
+    ) -> StdResult<Response> {
+        Ok(Response::new())
+    }
+    fn query(deps: Deps<TorramQuery>, _env: Env, _msg: Empty) -> StdResult<Binary> {
+        to_json_binary(&LEGACY_PRICES.load(deps.storage)?)
+    }
+    Box::new(ContractWrapper::new(execute, instantiate, query))
+}
+
+/// A contract that only records the given cw2 version.
+fn versioned_contract() -> Box<dyn Contract<Empty, TorramQuery>> {
+    fn instantiate(
+        deps: DepsMut<TorramQuery>,
+        _env: Env,
+        _info: MessageInfo,
+        msg: ContractVersion,
+    ) -> StdResult<Response> {
+        cw2::set_contract_version(deps.storage, msg.contract, msg.version)?;
+        Ok(Response::new())
+    }
+    fn execute(
+        _deps: DepsMut<TorramQuery>,
+        _env: Env,
+        _info: MessageInfo,
+        _msg: Empty,
+    ) -> StdResult<Response> {
+        Ok(Response::new())
+    }
+    fn query(_deps: Deps<TorramQuery>, _env: Env, _msg: Empty) -> StdResult<Binary> {
+        to_json_binary(&Empty {})
+    }
+    Box::new(ContractWrapper::new(execute, instantiate, query))
+}
+
+fn get_price(app: &TorramApp, contract: &Addr, symbol: &str) -> PriceResponse {
+    let msg = QueryMsg::GetPrice {
+        symbol: symbol.to_string(),
+    };
+    app.wrap().query_wasm_smart(contract, &msg).unwrap()
+}
+
+fn instantiate_versioned(app: &mut TorramApp, owner: &Addr, contract: &str, version: &str) -> Addr {
+    let code_id = app.store_code(versioned_contract());
+    let msg = ContractVersion {
+        contract: contract.to_string(),
+        version: version.to_string(),
+    };
+    app.instantiate_contract(
+        code_id,
+        owner.clone(),
+        &msg,
+        &[],
+        "versioned",
+        Some(owner.to_string()),
+    )
+    .unwrap()
+}
+
+#[test]
+fn failing_subscriber_does_not_block_updates() {
+    let mut app = mock_app();
+    let owner = app.api().addr_make("owner");
+    let btc = AssetInfo {
+        symbol: "BTC".to_string(),
+        class: AssetClass::Crypto,
+        decimals: 8,
+        enabled: true,
+        bounds: PriceBounds::default(),
+    };
+    let contract = instantiate_price(&mut app, &owner, vec![btc]);
+    let consumer_id = app.store_code(consumer_contract());
+    let mut consumers = vec![];
+    for fail in [false, true] {
+        let consumer = app
+            .instantiate_contract(consumer_id, owner.clone(), &fail, &[], "consumer", None)
+            .unwrap();
+        let msg = ExecuteMsg::Subscribe {
+            symbols: vec!["BTC".to_string()],
+            deviation_bps: 50,
+        };
+        app.execute_contract(consumer.clone(), contract.clone(), &msg, &[])
+            .unwrap();
+        consumers.push(consumer);
+    }
+
+    let msg = ExecuteMsg::UpdatePrices {
+        prices: vec![AssetPrice {
+            symbol: "BTC".to_string(),
+            price: Decimal::from_atomics(65000u128, 0).unwrap(),
+        }],
+    };
+    let res = app
+        .execute_contract(owner, contract.clone(), &msg, &[])
+        .unwrap();
+    assert!(res.events.iter().any(|e| e.ty == "wasm-subscriber_failed"));
+    assert_eq!(
+        get_price(&app, &contract, "BTC").price,
+        Decimal::from_atomics(65000u128, 0).unwrap()
+    );
+
+    let received: Vec<PriceResponse> = app
+        .wrap()
+        .query_wasm_smart(&consumers[0], &Empty {})
+        .unwrap();
+    assert_eq!(received, vec![get_price(&app, &contract, "BTC")]);
+    let list: SubscriptionsResponse = app
+        .wrap()
+        .query_wasm_smart(
+            &contract,
+            &QueryMsg::ListSubscriptions {
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap();
+    let failures = |addr: &Addr| {
+        let entry = list.subscriptions.iter().find(|s| s.subscriber == addr);
+        entry.unwrap().subscription.failures
+    };
+    assert_eq!((failures(&consumers[0]), failures(&consumers[1])), (0, 1));
+}
+
+#[test]
+fn migrate_converts_legacy_prices() {
+    let mut app = mock_app();
+    let owner = app.api().addr_make("owner");
+    let legacy_id = app.store_code(legacy_contract());
+    let price_id = app.store_code(price_contract());
+    let legacy = LegacyPriceData {
+        btc: Decimal::from_atomics(10929005u128, 2).unwrap(),
+        eth: Decimal::from_atomics(3000u128, 0).unwrap(),
+        usdc: Decimal::percent(99),
+        usdt: Decimal::one(),
+        dai: Decimal::zero(),
+    };
+    let contract = app
+        .instantiate_contract(
+            legacy_id,
+            owner.clone(),
+            &legacy,
+            &[],
+            "price",
+            Some(owner.to_string()),
+        )
+        .unwrap();
+
+    let stranger = app.api().addr_make("stranger");
+    app.migrate_contract(stranger, contract.clone(), &MigrateMsg {}, price_id)
+        .unwrap_err();
+    let res = app
+        .migrate_contract(owner.clone(), contract.clone(), &MigrateMsg {}, price_id)
+        .unwrap();
+    let wasm = res.events.iter().find(|e| e.ty == "wasm").unwrap();
+    assert!(wasm
+        .attributes
+        .iter()
+        .any(|a| a.key == "from_version" && a.value == "legacy"));
+
+    let version = cw2::query_contract_info(&app.wrap(), &contract).unwrap();
+    assert_eq!(version.contract, CONTRACT_NAME);
+    assert_eq!(version.version, CONTRACT_VERSION);
+    assert!(app
+        .wrap()
+        .query_wasm_raw(&contract, b"prices".to_vec())
+        .unwrap()
+        .is_none());
+
+    assert_eq!(get_price(&app, &contract, "BTC").price, legacy.btc);
+    assert_eq!(get_price(&app, &contract, "eth").price, legacy.eth);
+    assert_eq!(get_price(&app, &contract, "USDC").price, legacy.usdc);
+    let dai = get_price(&app, &contract, "DAI");
+    assert_eq!((dai.price, dai.last_updated_height), (Decimal::zero(), 0));
+    let usdc: AssetInfo = app
+        .wrap()
+        .query_wasm_smart(
+            &contract,
+            &QueryMsg::GetAsset {
+                symbol: "USDC".to_string(),
+            },
+        )
+        .unwrap();
+    assert_eq!(usdc.decimals, 6);
+
+    // the old admin keeps control and can feed prices right away
+    let roles: RolesResponse = app
+        .wrap()
+        .query_wasm_smart(&contract, &QueryMsg::GetRoles {})
+        .unwrap();
+    assert_eq!(roles.admin, owner);
+    let msg = ExecuteMsg::UpdatePrices {
+        prices: vec![AssetPrice {
+            symbol: "DAI".to_string(),
+            price: Decimal::one(),
+        }],
+    };
+    app.execute_contract(owner, contract.clone(), &msg, &[])
+        .unwrap();
+    assert_eq!(get_price(&app, &contract, "DAI").price, Decimal::one());
+}
+
+#[test]
+fn migrate_accepts_same_or_older_versions() {
+    let mut app = mock_app();
+    let owner = app.api().addr_make("owner");
+    let contract = instantiate_price(&mut app, &owner, vec![]);
+    let price_id = app.store_code(price_contract());
+    let version = cw2::query_contract_info(&app.wrap(), &contract).unwrap();
+    assert_eq!(version.version, CONTRACT_VERSION);
+    app.migrate_contract(owner.clone(), contract, &MigrateMsg {}, price_id)
+        .unwrap();
+
+    let older = instantiate_versioned(&mut app, &owner, CONTRACT_NAME, "0.0.1");
+    app.migrate_contract(owner, older.clone(), &MigrateMsg {}, price_id)
+        .unwrap();
+    let version = cw2::query_contract_info(&app.wrap(), &older).unwrap();
+    assert_eq!(version.version, CONTRACT_VERSION);
+}
+
+#[test]
+fn migrate_refuses_downgrades_and_other_contracts() {
+    let mut app = mock_app();
+    let owner = app.api().addr_make("owner");
+    let price_id = app.store_code(price_contract());
+
+    let newer = instantiate_versioned(&mut app, &owner, CONTRACT_NAME, "99.0.0");
+    let err = app
+        .migrate_contract(owner.clone(), newer, &MigrateMsg {}, price_id)
+        .unwrap_err();
+    assert!(matches!(
+        err.downcast_ref::<ContractError>(),
+        Some(ContractError::CannotDowngrade { from, .. }) if from == "99.0.0"
+    ));
+
+    let other = instantiate_versioned(&mut app, &owner, "crates.io:other", "0.0.1");
+    let err = app
+        .migrate_contract(owner, other, &MigrateMsg {}, price_id)
+        .unwrap_err();
+    assert!(matches!(
+        err.downcast_ref::<ContractError>(),
+        Some(ContractError::WrongContract { found, .. }) if found == "crates.io:other"
+    ));
+}
@@ -1,44 +1,564 @@
+use cosmwasm_schema::{cw_serde, QueryResponses};
 use cosmwasm_std::{
-    to_json_binary, Addr, Binary, CosmosMsg, CustomQuery, Deps, DepsMut, Decimal, Env, Event,
-    MessageInfo, QueryRequest, Response, StdError, StdResult, WasmMsg,
+    from_json, to_json_binary, to_json_vec, Addr, Api, Binary, CosmosMsg, CustomQuery, Decimal,
+    Decimal256, Deps, DepsMut, Empty, Env, Event, MessageInfo, Order, QuerierWrapper, Reply,
+    Response, StdError, StdResult, Storage, SubMsg, SubMsgResult, Timestamp, Uint128,
+    VerificationError, WasmMsg,
 };
-use cw_storage_plus::Item;
+use cw_storage_plus::{Bound, Item, Map};
 use schemars::JsonSchema;
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
-use serde_json::json;
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, BTreeSet};
 use thiserror::Error;
-use cosmwasm_schema::{cw_serde, QueryResponses};
+
+pub mod parser;
+
+use parser::{parse_prices, ParseError};
+
+pub use torram_bindings::{
+    Contact, ContactResponse, ContactsResponse, PricesAtHeightResponse, TorramQuerier, TorramQuery,
+    TssConsensusQuery,
+};
 
 #[derive(Error, Debug)]
 pub enum ContractError {
     #[error("{0}")]
     Std(#[from] StdError),
 
-    #[error("Unauthorized")]
-    Unauthorized {},
+    #[error("Only the admin can perform this action")]
+    NotAdmin {},
+
+    #[error("Sender is not the pending admin")]
+    NotPendingAdmin {},
+
+    #[error("No admin transfer is pending")]
+    NoPendingAdmin {},
+
+    #[error("Sender is not a whitelisted price feeder")]
+    NotFeeder {},
+
+    #[error("Sender is not a pauser")]
+    NotPauser {},
+
+    #[error("Sender is not a keeper")]
+    NotKeeper {},
+
+    #[error("Contract is paused")]
+    Paused {},
+
+    #[error("Invalid asset symbol: {symbol}")]
+    InvalidSymbol { symbol: String },
+
+    #[error("Asset {symbol} is already registered")]
+    AssetAlreadyRegistered { symbol: String },
+
+    #[error("Asset {symbol} is not registered")]
+    UnknownAsset { symbol: String },
+
+    #[error("Asset {symbol} is disabled")]
+    AssetDisabled { symbol: String },
+
+    #[error("Asset decimals must be at most {max}, got {decimals}")]
+    InvalidDecimals { decimals: u8, max: u8 },
+
+    #[error("Duplicate price for {symbol}")]
+    DuplicatePrice { symbol: String },
+
+    #[error("No prices supplied")]
+    EmptyPrices {},
+
+    #[error("Invalid price feed: {0}")]
+    InvalidFeed(#[from] ParseError),
+
+    #[error("Feed source height {source_height} is after the current height {height}")]
+    FutureSourceHeight { source_height: u64, height: u64 },
+
+    #[error("No oracle contacts found")]
+    NoOracleContacts {},
+
+    #[error("Only {got} oracle reporters for {symbol}, need {need}")]
+    InsufficientReporters { symbol: String, got: u32, need: u32 },
+
+    #[error("min_reporters must be at least 1")]
+    InvalidMinReporters {},
+
+    #[error("Price of {symbol} must be positive")]
+    ZeroPrice { symbol: String },
+
+    #[error("Invalid price bounds for {symbol}")]
+    InvalidBounds { symbol: String },
+
+    #[error("Invalid stablecoin basket member {symbol}: duplicate or non-positive weight")]
+    InvalidBasketMember { symbol: String },
+
+    #[error("depeg_threshold must be positive")]
+    InvalidDepegThreshold {},
+
+    #[error("Cannot set and clear depeg_threshold in the same update")]
+    ConflictingDepegThreshold {},
+
+    #[error("No stablecoin basket member has a price yet")]
+    EmptyBasket {},
+
+    #[error("Stablecoin basket average is too large to compute")]
+    BasketOverflow {},
+
+    #[error("Cannot migrate from contract {found}, expected {expected}")]
+    WrongContract { expected: String, found: String },
+
+    #[error("Cannot migrate from version {from} to older version {to}")]
+    CannotDowngrade { from: String, to: String },
+
+    #[error("Invalid contract version: {version}")]
+    InvalidVersion { version: String },
+
+    #[error("At most {max} subscriptions are allowed")]
+    TooManySubscriptions { max: u32 },
+
+    #[error("deviation_bps must be between 1 and 10000")]
+    InvalidDeviationBps {},
+
+    #[error("A subscription needs at least one symbol")]
+    NoSymbols {},
+
+    #[error("Sender has no subscription")]
+    NotSubscribed {},
+
+    #[error("{0}")]
+    Verification(#[from] VerificationError),
+
+    #[error("Public key must be a 33 or 65 byte SEC1 encoded secp256k1 key")]
+    InvalidPubkey {},
+
+    #[error("Feeder {feeder} has not registered a signing key")]
+    NoFeederKey { feeder: String },
+
+    #[error("Report signature does not match the feeder's key")]
+    InvalidSignature {},
+
+    #[error("Report nonce {got} must be greater than {last}")]
+    NonceReused { last: u64, got: u64 },
+
+    #[error("Report timestamp {timestamp} is in the future or more than {max_age}s old")]
+    ReportExpired { timestamp: Timestamp, max_age: u64 },
+
+    #[error("At most {max} symbols can be queried at once")]
+    TooManySymbols { max: u32 },
+
+    #[error("Unknown reply id {id}")]
+    UnknownReplyId { id: u64 },
+
+    #[error("Cross rate {base}/{quote} is out of range")]
+    CrossRateOverflow { base: String, quote: String },
+
+    #[error("No pending price for {symbol}")]
+    NoPendingPrice { symbol: String },
+
+    #[error("A pending price must be confirmed by a feeder that did not propose it")]
+    CannotConfirmOwnPrice {},
+
+    #[error("history_retention must be at least 1")]
+    InvalidHistoryRetention {},
+
+    #[error("No {symbol} price recorded at or before height {height}")]
+    NoPriceAt { symbol: String, height: u64 },
+
+    #[error("TWAP window must be at least one second")]
+    InvalidTwapWindow {},
+
+    #[error("Not enough {symbol} history to cover a {window_seconds}s TWAP")]
+    InsufficientHistory { symbol: String, window_seconds: u64 },
+
+    #[error("No price has been set for {symbol} yet")]
+    PriceNotSet { symbol: String },
+
+    #[error("Price of {symbol} is stale: last updated {age_seconds}s ago, max age is {max_age}s")]
+    StalePrice {
+        symbol: String,
+        age_seconds: u64,
+        max_age: u64,
+    },
+
+    #[error("Invalid max age for {class:?}: must be positive and set once per class")]
+    InvalidMaxAge { class: AssetClass },
+
+    #[error("Quorum must be between 1 and the number of feeders ({feeders}), got {quorum}")]
+    InvalidQuorum { quorum: u32, feeders: u32 },
+
+    #[error("Round {got} is not open, the current round is {expected}")]
+    WrongRound { expected: u64, got: u64 },
+
+    #[error("Feeder already submitted prices for round {round_id}")]
+    AlreadySubmitted { round_id: u64 },
+}
+
+/// Errors returned by the `CwTemplateContract` query helpers.
+#[derive(Error, Debug)]
+pub enum OracleQueryError {
+    #[error("Price contract query {query} failed: {source}")]
+    Query {
+        query: &'static str,
+        source: StdError,
+    },
+
+    #[error("No price has been set for {symbol} yet")]
+    PriceNotSet { symbol: String },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum AssetClass {
+    Crypto,
+    Rwa,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct AssetInfo {
+    pub symbol: String,
+    pub class: AssetClass,
+    pub decimals: u8,
+    pub enabled: bool,
+    #[serde(default)]
+    pub bounds: PriceBounds,
+}
+
+/// Sanity limits applied to every new price of an asset; `None` disables a check.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Default, JsonSchema)]
+pub struct PriceBounds {
+    /// Largest accepted move from the stored price as a fraction, e.g. `0.1` for 10%.
+    /// Larger moves are held in `PENDING_PRICES` until confirmed by `ForceUpdate`.
+    pub max_deviation: Option<Decimal>,
+    /// Prices outside `[min_price, max_price]` are rejected outright.
+    pub min_price: Option<Decimal>,
+    pub max_price: Option<Decimal>,
+}
+
+/// A price that moved more than `max_deviation` and awaits confirmation.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PendingPrice {
+    pub symbol: String,
+    pub price: Decimal,
+    /// Stored price the deviation was measured against.
+    pub previous: Decimal,
+    /// Feeders (or keeper) whose submission produced the price.
+    pub proposed_by: Vec<Addr>,
+    pub height: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PendingPricesResponse {
+    pub prices: Vec<PendingPrice>,
 }
 
+/// A consumer contract pushed `SubscriberMsg::OraclePriceUpdate` when one of
+/// `symbols` moves by at least `deviation_bps` from the price last pushed.
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
-pub struct TorramQueryWrapper {
-    pub method: String,
-    pub args: serde_json::Value,
+pub struct Subscription {
+    pub symbols: Vec<String>,
+    pub deviation_bps: u32,
+    /// Price each symbol was last pushed at, or held when subscribing.
+    pub notified: Vec<AssetPrice>,
+    /// Callbacks that failed; failures never block a price update.
+    pub failures: u32,
 }
 
-impl CustomQuery for TorramQueryWrapper {}
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SubscriptionResponse {
+    pub subscriber: Addr,
+    pub subscription: Subscription,
+}
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
-pub struct Contact {
-    pub address: String,
-    pub contact: String,
+pub struct SubscriptionsResponse {
+    pub subscriptions: Vec<SubscriptionResponse>,
+}
+
+/// Executed on subscribers; consumer contracts add this variant to their
+/// `ExecuteMsg`.
+#[cw_serde]
+pub enum SubscriberMsg {
+    OraclePriceUpdate { prices: Vec<PriceResponse> },
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
-pub struct ContactsResponse {
-    pub contacts: Vec<Contact>,
+pub struct AssetPrice {
+    pub symbol: String,
+    pub price: Decimal,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct PriceData {
+    pub prices: Vec<AssetPrice>,
+}
+
+/// Outcome for one symbol of `GetPricesFor`: exactly one of `price` and
+/// `error` is set.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PriceResult {
+    pub symbol: String,
+    pub price: Option<PriceResponse>,
+    pub error: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PricesForResponse {
+    /// In the order the symbols were requested.
+    pub prices: Vec<PriceResult>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct AssetsResponse {
+    pub assets: Vec<AssetInfo>,
+}
+
+/// Latest price of an asset and the block it was written in.
+///
+/// Assets that have never been updated hold the zero placeholder written at
+/// registration, with `last_updated_height` 0.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct StoredPrice {
+    pub price: Decimal,
+    pub last_updated_height: u64,
+    pub last_updated_time: Timestamp,
+}
+
+impl StoredPrice {
+    pub fn is_placeholder(&self) -> bool {
+        self.last_updated_height == 0 || self.price.is_zero()
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PriceResponse {
+    pub symbol: String,
+    pub price: Decimal,
+    pub last_updated_height: u64,
+    pub last_updated_time: Timestamp,
+}
+
+/// Price of one unit of `base` expressed in `quote`.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct CrossRateResponse {
+    pub base: String,
+    pub quote: String,
+    /// `base / quote`, rounded down to `decimals` places.
+    pub rate: Decimal,
+    /// The quote asset's decimals.
+    pub decimals: u8,
+    /// Update time of the older of the two legs.
+    pub last_updated_time: Timestamp,
+}
+
+/// Oldest acceptable price, in seconds, for assets of `class`.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct MaxAge {
+    pub class: AssetClass,
+    pub seconds: u64,
+}
+
+/// Result of `FetchFromOracle`: the per-asset median plus what each reporter said.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct OraclePrices {
+    pub prices: Vec<AssetPrice>,
+    /// Prices parsed from each `Contact`, keyed by `Contact.address`.
+    pub reporters: BTreeMap<String, Vec<AssetPrice>>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Config {
+    /// Minimum number of reporters that must quote an asset for its median to be used.
+    pub min_reporters: u32,
+    /// Addresses allowed to call `SyncFromOracle`; anyone may sync while this is empty.
+    pub keepers: Vec<Addr>,
+    /// Number of history points kept per asset; older points are pruned on update.
+    pub history_retention: u32,
+    /// Staleness limits used by `GetPriceChecked`; classes not listed never go stale.
+    pub max_age: Vec<MaxAge>,
+    /// Number of feeder submissions that finalizes a round.
+    pub quorum: u32,
+    /// Stablecoins averaged into `GetBasketPrice` and the `oracle_prices` event.
+    pub basket: Vec<BasketMember>,
+    /// Largest accepted distance of a basket member from 1.0 before a
+    /// `stablecoin_depeg` event is emitted; `None` disables the alert.
+    pub depeg_threshold: Option<Decimal>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct BasketMember {
+    pub symbol: String,
+    /// Defaults to 1, i.e. members are averaged equally.
+    pub weight: Option<Decimal>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct BasketMemberPrice {
+    pub symbol: String,
+    pub price: Decimal,
+    pub weight: Decimal,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct BasketPriceResponse {
+    /// Weighted average of `members`.
+    pub price: Decimal,
+    /// Basket members that have a price; the others are left out of the average.
+    pub members: Vec<BasketMemberPrice>,
+}
+
+/// Fields of `Config` to change; `None` leaves the current value.
+#[cw_serde]
+#[derive(Default)]
+pub struct ConfigUpdate {
+    pub min_reporters: Option<u32>,
+    pub keepers: Option<Vec<String>>,
+    pub history_retention: Option<u32>,
+    pub max_age: Option<Vec<MaxAge>>,
+    pub quorum: Option<u32>,
+    pub basket: Option<Vec<BasketMember>>,
+    pub depeg_threshold: Option<Decimal>,
+    /// Turns depeg alerts off by removing `depeg_threshold`.
+    #[serde(default)]
+    pub clear_depeg_threshold: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Submission {
+    pub feeder: Addr,
+    pub prices: Vec<AssetPrice>,
+}
+
+/// Feeder submissions for one round. Only the open round and the last
+/// finalized one are kept.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Round {
+    pub round_id: u64,
+    pub submissions: Vec<Submission>,
+    pub finalized_height: Option<u64>,
+    /// Per-asset medians stored when the round was finalized.
+    pub prices: Vec<AssetPrice>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Default, JsonSchema)]
+pub struct FeederStats {
+    pub submissions: u64,
+    /// Submitted rounds that went on to be finalized.
+    pub finalized_rounds: u64,
+    pub last_round: u64,
+    pub last_submission_height: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct FeederResponse {
+    pub address: Addr,
+    pub stats: FeederStats,
+    /// Key checked against the feeder's signed reports, if registered.
+    pub pubkey: Option<Binary>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct FeederKey {
+    /// SEC1 encoded secp256k1 public key.
+    pub pubkey: Binary,
+    /// Nonce of the last accepted signed report.
+    pub last_nonce: u64,
+}
+
+/// Prices a feeder signs off-chain so that anyone can relay them with
+/// `SubmitSignedReport`.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PriceReport {
+    pub feeder: String,
+    /// Must be the open round when the report is relayed.
+    pub round_id: u64,
+    /// Must be greater than the nonce of the feeder's previous report.
+    pub nonce: u64,
+    /// Reports are accepted up to `MAX_REPORT_AGE` seconds after this time.
+    pub timestamp: Timestamp,
+    pub prices: Vec<AssetPrice>,
+}
+
+/// The signed form of a `PriceReport`, bound to one chain and contract.
+#[derive(Serialize)]
+struct ReportPayload<'a> {
+    chain_id: &'a str,
+    contract: &'a str,
+    feeder: &'a str,
+    round_id: u64,
+    nonce: u64,
+    timestamp: Timestamp,
+    prices: &'a [AssetPrice],
+}
+
+impl PriceReport {
+    /// Bytes whose SHA-256 digest the feeder signs: compact JSON of `chain_id`,
+    /// `contract`, `feeder`, `round_id`, `nonce`, `timestamp` and `prices`, in
+    /// that order.
+    pub fn sign_bytes(&self, chain_id: &str, contract: &Addr) -> StdResult<Vec<u8>> {
+        to_json_vec(&ReportPayload {
+            chain_id,
+            contract: contract.as_str(),
+            feeder: &self.feeder,
+            round_id: self.round_id,
+            nonce: self.nonce,
+            timestamp: self.timestamp,
+            prices: &self.prices,
+        })
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct FeedersResponse {
+    pub feeders: Vec<FeederResponse>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RolesResponse {
+    pub admin: Addr,
+    /// Address proposed by `ProposeAdmin` that has not accepted yet.
+    pub pending_admin: Option<Addr>,
+    pub pausers: Vec<Addr>,
+    pub paused: bool,
+}
+
+/// A stored price together with the block it was written in.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PricePoint {
+    pub height: u64,
+    pub time: Timestamp,
+    pub price: Decimal,
+    /// Value of the asset's price accumulator at `time`, before `price` took effect.
+    pub cumulative: Decimal256,
+}
+
+/// Running sum of `price * seconds` for one asset, advanced on every update.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Accumulator {
+    pub cumulative: Decimal256,
+    /// Price in effect since `time`.
+    pub price: Decimal,
+    pub time: Timestamp,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct TwapResponse {
+    pub symbol: String,
+    pub twap: Decimal,
+    pub window_seconds: u64,
+    /// Block time the window ends at.
+    pub end_time: Timestamp,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PriceHistoryResponse {
+    pub symbol: String,
+    pub points: Vec<PricePoint>,
+}
+
+/// Single-item price layout written before the asset registry existed.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct LegacyPriceData {
     pub btc: Decimal,
     pub eth: Decimal,
     pub usdc: Decimal,
@@ -46,23 +566,162 @@ pub struct PriceData {
     pub dai: Decimal,
 }
 
-pub const PRICES: Item<PriceData> = Item::new("prices");
+/// Legacy prices, read and removed by `migrate`.
+pub const LEGACY_PRICES: Item<LegacyPriceData> = Item::new("prices");
+
+/// Registered feeds keyed by their upper-case symbol.
+pub const ASSETS: Map<&str, AssetInfo> = Map::new("assets");
+/// Latest price per registered symbol.
+pub const PRICES: Map<&str, StoredPrice> = Map::new("asset_prices");
 pub const ADMIN: Item<Addr> = Item::new("admin");
+/// Set by `ProposeAdmin` and cleared once the new admin accepts.
+pub const PENDING_ADMIN: Item<Addr> = Item::new("pending_admin");
+/// Addresses allowed to `Pause` and `Unpause` the contract.
+pub const PAUSERS: Map<&Addr, Empty> = Map::new("pausers");
+pub const PAUSED: Item<bool> = Item::new("paused");
+pub const CONFIG: Item<Config> = Item::new("config");
+/// Price history keyed by `(symbol, block height)`.
+pub const PRICE_HISTORY: Map<(&str, u64), PricePoint> = Map::new("price_history");
+/// Number of points currently held in `PRICE_HISTORY` per symbol.
+pub const HISTORY_LEN: Map<&str, u32> = Map::new("history_len");
+pub const ACCUMULATORS: Map<&str, Accumulator> = Map::new("accumulators");
+/// Whitelisted price feeders and their participation.
+pub const FEEDERS: Map<&Addr, FeederStats> = Map::new("feeders");
+pub const CURRENT_ROUND: Item<u64> = Item::new("current_round");
+pub const ROUNDS: Map<u64, Round> = Map::new("rounds");
+/// Prices held back by the deviation check, keyed by symbol.
+pub const PENDING_PRICES: Map<&str, PendingPrice> = Map::new("pending_prices");
+pub const SUBSCRIPTIONS: Map<&Addr, Subscription> = Map::new("subscriptions");
+/// Kept when a feeder is removed, so that re-adding it cannot reset its nonce.
+pub const FEEDER_KEYS: Map<&Addr, FeederKey> = Map::new("feeder_keys");
+
+/// Symbols longer than this are rejected when registering an asset.
+pub const MAX_SYMBOL_LEN: usize = 12;
+/// Prices are stored as `Decimal`, so no feed can be more precise than this.
+pub const MAX_DECIMALS: u8 = 18;
+
+pub const DEFAULT_HISTORY_RETENTION: u32 = 100;
+const DEFAULT_LIMIT: u32 = 10;
+const MAX_LIMIT: u32 = 100;
+const NANOS_PER_SECOND: u64 = 1_000_000_000;
+
+/// Every subscriber is checked on each update, so their number is capped.
+pub const MAX_SUBSCRIPTIONS: u32 = 50;
+/// Signed reports older than this many seconds are rejected.
+pub const MAX_REPORT_AGE: u64 = 300;
+/// Gas available to a subscriber callback, so a subscriber running out of gas
+/// fails its own submessage instead of the whole update.
+pub const SUBSCRIBER_GAS_LIMIT: u64 = 300_000;
+pub const SUBSCRIBER_REPLY_ID: u64 = 1;
+
+pub const CONTRACT_NAME: &str = "crates.io:price";
+pub const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// Bumped whenever the attributes of the `oracle_prices` event change.
+pub const PRICES_EVENT_VERSION: &str = "2";
+
+/// Stablecoin basket used when none is given at instantiation.
+const DEFAULT_BASKET: [&str; 3] = ["USDC", "USDT", "DAI"];
 
 #[cw_serde]
 pub struct InstantiateMsg {
     pub admin: String,
+    /// Assets registered (with a zero price) at instantiation.
+    #[serde(default)]
+    pub assets: Vec<AssetInfo>,
+    /// Defaults to 1, i.e. a single reporter is enough.
+    pub min_reporters: Option<u32>,
+    /// Defaults to `DEFAULT_HISTORY_RETENTION` points per asset.
+    pub history_retention: Option<u32>,
+    #[serde(default)]
+    pub max_age: Vec<MaxAge>,
+    /// Defaults to the admin alone.
+    pub feeders: Option<Vec<String>>,
+    /// Defaults to 1, so each submission is stored immediately.
+    pub quorum: Option<u32>,
+    /// Defaults to USDC, USDT and DAI with equal weights.
+    pub basket: Option<Vec<BasketMember>>,
+    pub depeg_threshold: Option<Decimal>,
+    /// Defaults to the admin alone.
+    pub pausers: Option<Vec<String>>,
 }
 
 #[cw_serde]
 pub enum ExecuteMsg {
+    /// Submits `prices` to the current round.
     UpdatePrices {
-        btc: Decimal,
-        eth: Decimal,
-        usdc: Decimal,
-        usdt: Decimal,
-        dai: Decimal,
+        prices: Vec<AssetPrice>,
+    },
+    /// Like `UpdatePrices`, but takes a `tssconsensus` contact string such as
+    /// `"BTC:109290.5,ETH:2627.48"`. Symbols that are not registered and
+    /// enabled are ignored.
+    UpdatePricesRaw {
+        feed: String,
+        /// Height the feed was read at; must not be in the future.
+        source_height: u64,
+    },
+    /// Submits `prices` to `round_id`, which must be the current round.
+    SubmitPrices {
+        round_id: u64,
+        prices: Vec<AssetPrice>,
+    },
+    /// Submits a report signed by `report.feeder` on its behalf; anyone may
+    /// relay it.
+    SubmitSignedReport {
+        report: PriceReport,
+        /// 64 byte secp256k1 signature over the SHA-256 of `report.sign_bytes`.
+        signature: Binary,
+    },
+    /// Registers the key that verifies the sending feeder's signed reports.
+    SetFeederKey {
+        pubkey: Binary,
+    },
+    RegisterAsset {
+        symbol: String,
+        class: AssetClass,
+        decimals: u8,
+        enabled: bool,
+        #[serde(default)]
+        bounds: PriceBounds,
+    },
+    UpdateAsset {
+        symbol: String,
+        class: Option<AssetClass>,
+        decimals: Option<u8>,
+        enabled: Option<bool>,
+        bounds: Option<PriceBounds>,
+    },
+    UpdateConfig(ConfigUpdate),
+    UpdateFeeders {
+        add: Vec<String>,
+        remove: Vec<String>,
     },
+    /// Fetches the aggregated oracle prices and stores them, like `UpdatePrices`.
+    SyncFromOracle {},
+    /// Stores the pending price of `symbol`. Allowed for the admin and for
+    /// feeders that did not propose it.
+    ForceUpdate {
+        symbol: String,
+    },
+    /// Starts a two-step admin transfer; `new_admin` must call `AcceptAdmin`.
+    ProposeAdmin {
+        new_admin: String,
+    },
+    AcceptAdmin {},
+    UpdatePausers {
+        add: Vec<String>,
+        remove: Vec<String>,
+    },
+    /// Blocks price updates and makes `GetPriceChecked` fail until `Unpause`.
+    Pause {},
+    Unpause {},
+    /// Registers the sender for `SubscriberMsg::OraclePriceUpdate` callbacks,
+    /// replacing its previous subscription.
+    Subscribe {
+        symbols: Vec<String>,
+        deviation_bps: u32,
+    },
+    Unsubscribe {},
 }
 
 #[cw_serde]
@@ -70,10 +729,70 @@ pub enum ExecuteMsg {
 pub enum QueryMsg {
     #[returns(PriceData)]
     GetPrices {},
-    #[returns(Binary)]
+    #[returns(PriceResponse)]
+    GetPrice { symbol: String },
+    /// Like `GetPrice`, but fails if the price was never set or is older than
+    /// the `max_age` configured for the asset's class.
+    #[returns(PriceResponse)]
+    GetPriceChecked { symbol: String },
+    /// Checked prices of `symbols`, reporting failures per symbol instead of
+    /// failing the query. At most 100 symbols.
+    #[returns(PricesForResponse)]
+    GetPricesFor { symbols: Vec<String> },
+    /// Price of `base` in units of `quote`, from the checked prices of both.
+    #[returns(CrossRateResponse)]
+    GetCrossRate { base: String, quote: String },
+    #[returns(AssetInfo)]
+    GetAsset { symbol: String },
+    /// Registered assets, enabled or not, ordered by symbol.
+    #[returns(AssetsResponse)]
+    ListAssets {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    #[returns(Config)]
+    GetConfig {},
+    /// Latest price recorded at or before `height`.
+    #[returns(PricePoint)]
+    GetPriceAt { symbol: String, height: u64 },
+    /// Recorded prices with `start <= height <= end`, oldest first.
+    #[returns(PriceHistoryResponse)]
+    GetPriceHistory {
+        symbol: String,
+        start: Option<u64>,
+        end: Option<u64>,
+        limit: Option<u32>,
+    },
+    /// Time-weighted average price over the last `window_seconds`.
+    #[returns(TwapResponse)]
+    GetTwap { symbol: String, window_seconds: u64 },
+    /// The given round, or the open one when `round_id` is omitted.
+    #[returns(Round)]
+    GetRound { round_id: Option<u64> },
+    #[returns(FeedersResponse)]
+    ListFeeders {},
+    #[returns(RolesResponse)]
+    GetRoles {},
+    /// Weighted average price of the stablecoin basket.
+    #[returns(BasketPriceResponse)]
+    GetBasketPrice {},
+    /// Prices held back by the deviation check.
+    #[returns(PendingPricesResponse)]
+    ListPendingPrices {},
+    #[returns(Subscription)]
+    GetSubscription { subscriber: String },
+    #[returns(SubscriptionsResponse)]
+    ListSubscriptions {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    #[returns(OraclePrices)]
     FetchFromOracle {},
 }
 
+#[cw_serde]
+pub struct MigrateMsg {}
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
 pub struct CwTemplateContract(pub Addr);
 
@@ -91,6 +810,209 @@ impl CwTemplateContract {
         }
         .into())
     }
+
+    fn query<Q: CustomQuery, T: DeserializeOwned>(
+        &self,
+        querier: &QuerierWrapper<Q>,
+        query: &'static str,
+        msg: &QueryMsg,
+    ) -> Result<T, OracleQueryError> {
+        querier
+            .query_wasm_smart(self.addr(), msg)
+            .map_err(|source| OracleQueryError::Query { query, source })
+    }
+
+    /// Latest prices of every enabled asset.
+    pub fn prices<Q: CustomQuery>(
+        &self,
+        querier: &QuerierWrapper<Q>,
+    ) -> Result<PriceData, OracleQueryError> {
+        self.query(querier, "get_prices", &QueryMsg::GetPrices {})
+    }
+
+    /// Latest price of `symbol`, failing with `PriceNotSet` while the asset
+    /// still holds its zero registration placeholder.
+    pub fn price<Q: CustomQuery>(
+        &self,
+        querier: &QuerierWrapper<Q>,
+        symbol: &str,
+    ) -> Result<PriceResponse, OracleQueryError> {
+        let msg = QueryMsg::GetPrice {
+            symbol: symbol.to_string(),
+        };
+        let price: PriceResponse = self.query(querier, "get_price", &msg)?;
+        if price.price.is_zero() {
+            return Err(OracleQueryError::PriceNotSet {
+                symbol: price.symbol,
+            });
+        }
+        Ok(price)
+    }
+
+    /// Latest price of `symbol`, which the contract rejects if stale or paused.
+    pub fn price_checked<Q: CustomQuery>(
+        &self,
+        querier: &QuerierWrapper<Q>,
+        symbol: &str,
+    ) -> Result<PriceResponse, OracleQueryError> {
+        let msg = QueryMsg::GetPriceChecked {
+            symbol: symbol.to_string(),
+        };
+        self.query(querier, "get_price_checked", &msg)
+    }
+
+    /// Checked prices of `symbols`, with an error message for each symbol
+    /// that has no usable price.
+    pub fn prices_for<Q: CustomQuery>(
+        &self,
+        querier: &QuerierWrapper<Q>,
+        symbols: &[&str],
+    ) -> Result<PricesForResponse, OracleQueryError> {
+        let msg = QueryMsg::GetPricesFor {
+            symbols: symbols.iter().map(|s| s.to_string()).collect(),
+        };
+        self.query(querier, "get_prices_for", &msg)
+    }
+
+    /// Prices aggregated live from the Torram oracle module, without storing them.
+    pub fn fetch_from_oracle<Q: CustomQuery>(
+        &self,
+        querier: &QuerierWrapper<Q>,
+    ) -> Result<OraclePrices, OracleQueryError> {
+        self.query(querier, "fetch_from_oracle", &QueryMsg::FetchFromOracle {})
+    }
+}
+
+/// Test doubles for contracts that read prices through `CwTemplateContract`.
+#[cfg(all(any(test, feature = "library"), not(target_arch = "wasm32")))]
+pub mod testing {
+    use super::*;
+    use cosmwasm_std::testing::mock_env;
+    use cosmwasm_std::{ContractResult, QuerierResult, SystemError, SystemResult, WasmQuery};
+
+    /// Answers the price queries sent to `addr` from in-memory prices.
+    ///
+    /// Install it with `deps.querier.update_wasm(oracle.handler())`.
+    #[derive(Clone, Debug, Default)]
+    pub struct MockOracle {
+        pub addr: String,
+        /// Keyed by upper-case symbol.
+        pub prices: BTreeMap<String, PriceResponse>,
+    }
+
+    impl MockOracle {
+        pub fn new(addr: impl Into<String>) -> Self {
+            MockOracle {
+                addr: addr.into(),
+                prices: BTreeMap::new(),
+            }
+        }
+
+        /// Adds `symbol` at `price`, last updated in the `mock_env` block.
+        pub fn with_price(self, symbol: &str, price: Decimal) -> Self {
+            let block = mock_env().block;
+            self.with_price_at(symbol, price, block.height, block.time)
+        }
+
+        pub fn with_price_at(
+            mut self,
+            symbol: &str,
+            price: Decimal,
+            height: u64,
+            time: Timestamp,
+        ) -> Self {
+            let symbol = symbol.to_ascii_uppercase();
+            self.prices.insert(
+                symbol.clone(),
+                PriceResponse {
+                    symbol,
+                    price,
+                    last_updated_height: height,
+                    last_updated_time: time,
+                },
+            );
+            self
+        }
+
+        pub fn handler(self) -> impl Fn(&WasmQuery) -> QuerierResult {
+            move |request| self.handle(request)
+        }
+
+        pub fn handle(&self, request: &WasmQuery) -> QuerierResult {
+            let WasmQuery::Smart { contract_addr, msg } = request else {
+                return SystemResult::Err(SystemError::UnsupportedRequest {
+                    kind: "non-smart wasm query".to_string(),
+                });
+            };
+            if *contract_addr != self.addr {
+                return SystemResult::Err(SystemError::NoSuchContract {
+                    addr: contract_addr.clone(),
+                });
+            }
+            let response = from_json(msg)
+                .map_err(ContractError::from)
+                .and_then(|msg| self.query(msg))
+                .map_err(|err| err.to_string());
+            SystemResult::Ok(ContractResult::from(response))
+        }
+
+        fn query(&self, msg: QueryMsg) -> Result<Binary, ContractError> {
+            let prices = || {
+                self.prices
+                    .values()
+                    .map(|p| AssetPrice {
+                        symbol: p.symbol.clone(),
+                        price: p.price,
+                    })
+                    .collect()
+            };
+            match msg {
+                QueryMsg::GetPrices {} => Ok(to_json_binary(&PriceData { prices: prices() })?),
+                QueryMsg::GetPrice { symbol } => Ok(to_json_binary(self.load(&symbol)?)?),
+                QueryMsg::GetPriceChecked { symbol } => Ok(to_json_binary(self.checked(&symbol)?)?),
+                QueryMsg::GetPricesFor { symbols } => {
+                    let prices = symbols
+                        .into_iter()
+                        .map(|symbol| match self.checked(&symbol) {
+                            Ok(price) => PriceResult {
+                                symbol: price.symbol.clone(),
+                                price: Some(price.clone()),
+                                error: None,
+                            },
+                            Err(err) => PriceResult {
+                                symbol,
+                                price: None,
+                                error: Some(err.to_string()),
+                            },
+                        })
+                        .collect();
+                    Ok(to_json_binary(&PricesForResponse { prices })?)
+                }
+                QueryMsg::FetchFromOracle {} => Ok(to_json_binary(&OraclePrices {
+                    prices: prices(),
+                    reporters: BTreeMap::new(),
+                })?),
+                _ => Err(StdError::generic_err("query not supported by MockOracle").into()),
+            }
+        }
+
+        fn checked(&self, symbol: &str) -> Result<&PriceResponse, ContractError> {
+            let price = self.load(symbol)?;
+            if price.price.is_zero() {
+                return Err(ContractError::PriceNotSet {
+                    symbol: price.symbol.clone(),
+                });
+            }
+            Ok(price)
+        }
+
+        fn load(&self, symbol: &str) -> Result<&PriceResponse, ContractError> {
+            let symbol = normalize_symbol(symbol)?;
+            self.prices
+                .get(&symbol)
+                .ok_or(ContractError::UnknownAsset { symbol })
+        }
+    }
 }
 
 #[cfg(not(feature = "library"))]
@@ -98,24 +1020,65 @@ use cosmwasm_std::entry_point;
 
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn instantiate(
-    deps: DepsMut,
+    deps: DepsMut<TorramQuery>,
     _env: Env,
     _info: MessageInfo,
     msg: InstantiateMsg,
-) -> StdResult<Response> {
+) -> Result<Response, ContractError> {
     deps.api.debug("reached out to here (instantiate)");
+    cw2::set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
     let admin = deps.api.addr_validate(&msg.admin)?;
     ADMIN.save(deps.storage, &admin)?;
-    PRICES.save(
+    let min_reporters = msg.min_reporters.unwrap_or(1);
+    if min_reporters == 0 {
+        return Err(ContractError::InvalidMinReporters {});
+    }
+    let history_retention = msg.history_retention.unwrap_or(DEFAULT_HISTORY_RETENTION);
+    if history_retention == 0 {
+        return Err(ContractError::InvalidHistoryRetention {});
+    }
+    validate_max_age(&msg.max_age)?;
+    let feeders = match msg.feeders {
+        Some(feeders) => feeders
+            .iter()
+            .map(|addr| deps.api.addr_validate(addr))
+            .collect::<StdResult<Vec<_>>>()?,
+        None => vec![admin.clone()],
+    };
+    for feeder in &feeders {
+        FEEDERS.save(deps.storage, feeder, &FeederStats::default())?;
+    }
+    let pausers = match msg.pausers {
+        Some(pausers) => pausers
+            .iter()
+            .map(|addr| deps.api.addr_validate(addr))
+            .collect::<StdResult<Vec<_>>>()?,
+        None => vec![admin.clone()],
+    };
+    for pauser in &pausers {
+        PAUSERS.save(deps.storage, pauser, &Empty {})?;
+    }
+    PAUSED.save(deps.storage, &false)?;
+    let quorum = msg.quorum.unwrap_or(1);
+    ensure_quorum_reachable(deps.storage, quorum)?;
+    let basket = validate_basket(msg.basket.unwrap_or_else(default_basket))?;
+    validate_depeg_threshold(msg.depeg_threshold)?;
+    CONFIG.save(
         deps.storage,
-        &PriceData {
-            btc: Decimal::zero(),
-            eth: Decimal::zero(),
-            usdc: Decimal::zero(),
-            usdt: Decimal::zero(),
-            dai: Decimal::zero(),
+        &Config {
+            min_reporters,
+            keepers: vec![],
+            history_retention,
+            max_age: msg.max_age,
+            quorum,
+            basket,
+            depeg_threshold: msg.depeg_threshold,
         },
     )?;
+    CURRENT_ROUND.save(deps.storage, &1)?;
+    for asset in msg.assets {
+        register_asset(deps.storage, asset)?;
+    }
     Ok(Response::new()
         .add_attribute("method", "instantiate")
         .add_attribute("admin", msg.admin))
@@ -123,99 +1086,3088 @@ pub fn instantiate(
 
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn execute(
-    deps: DepsMut<TorramQueryWrapper>,
-    _env: Env,
+    deps: DepsMut<TorramQuery>,
+    env: Env,
     info: MessageInfo,
     msg: ExecuteMsg,
-) -> Result<Response, StdError> {
+) -> Result<Response, ContractError> {
     match msg {
-        ExecuteMsg::UpdatePrices {
-            btc,
-            eth,
-            usdc,
-            usdt,
-            dai,
-        } => try_update_prices(deps, info, btc, eth, usdc, usdt, dai),
+        ExecuteMsg::UpdatePrices { prices } => try_update_prices(deps, env, info, prices),
+        ExecuteMsg::UpdatePricesRaw {
+            feed,
+            source_height,
+        } => try_update_prices_raw(deps, env, info, feed, source_height),
+        ExecuteMsg::SubmitPrices { round_id, prices } => {
+            try_submit_prices(deps, env, info, round_id, prices)
+        }
+        ExecuteMsg::SubmitSignedReport { report, signature } => {
+            try_submit_signed_report(deps, env, info, report, signature)
+        }
+        ExecuteMsg::SetFeederKey { pubkey } => try_set_feeder_key(deps, info, pubkey),
+        ExecuteMsg::RegisterAsset {
+            symbol,
+            class,
+            decimals,
+            enabled,
+            bounds,
+        } => try_register_asset(
+            deps,
+            info,
+            AssetInfo {
+                symbol,
+                class,
+                decimals,
+                enabled,
+                bounds,
+            },
+        ),
+        ExecuteMsg::UpdateAsset {
+            symbol,
+            class,
+            decimals,
+            enabled,
+            bounds,
+        } => try_update_asset(deps, info, symbol, class, decimals, enabled, bounds),
+        ExecuteMsg::UpdateConfig(update) => try_update_config(deps, info, update),
+        ExecuteMsg::UpdateFeeders { add, remove } => try_update_feeders(deps, info, add, remove),
+        ExecuteMsg::SyncFromOracle {} => try_sync_from_oracle(deps, env, info),
+        ExecuteMsg::ForceUpdate { symbol } => try_force_update(deps, env, info, symbol),
+        ExecuteMsg::Subscribe {
+            symbols,
+            deviation_bps,
+        } => try_subscribe(deps, info, symbols, deviation_bps),
+        ExecuteMsg::Unsubscribe {} => {
+            if !SUBSCRIPTIONS.has(deps.storage, &info.sender) {
+                return Err(ContractError::NotSubscribed {});
+            }
+            SUBSCRIPTIONS.remove(deps.storage, &info.sender);
+            Ok(Response::new()
+                .add_attribute("action", "unsubscribe")
+                .add_attribute("subscriber", info.sender))
+        }
+        ExecuteMsg::ProposeAdmin { new_admin } => try_propose_admin(deps, info, new_admin),
+        ExecuteMsg::AcceptAdmin {} => try_accept_admin(deps, info),
+        ExecuteMsg::UpdatePausers { add, remove } => try_update_pausers(deps, info, add, remove),
+        ExecuteMsg::Pause {} => try_set_paused(deps, info, true),
+        ExecuteMsg::Unpause {} => try_set_paused(deps, info, false),
     }
 }
 
-fn try_update_prices(
-    deps: DepsMut<TorramQueryWrapper>,
-    info: MessageInfo,
-    btc: Decimal,
-    eth: Decimal,
-    usdc: Decimal,
-    usdt: Decimal,
-    dai: Decimal,
-) -> Result<Response, StdError> {
-    let admin = ADMIN.load(deps.storage)?;
-    if info.sender != admin {
-        return Err(StdError::generic_err("Unauthorized"));
+fn ensure_admin(storage: &dyn Storage, sender: &Addr) -> Result<(), ContractError> {
+    if *sender != ADMIN.load(storage)? {
+        return Err(ContractError::NotAdmin {});
     }
-    let data = PriceData { btc, eth, usdc, usdt, dai };
-    PRICES.save(deps.storage, &data)?;
-
-    let avg = average_price(&data);
-
-    Ok(Response::new()
-        .add_attribute("action", "update_prices")
-        .add_attribute("sender", info.sender)
-        .add_event(
-            Event::new("oracle_prices")
-                .add_attribute("usdc", data.usdc.to_string())
-                .add_attribute("usdt", data.usdt.to_string())
-                .add_attribute("dai", data.dai.to_string())
-                .add_attribute("average", avg.to_string()),
-        ))
+    Ok(())
 }
 
-fn average_price(prices: &PriceData) -> Decimal {
-    (prices.usdc + prices.usdt + prices.dai) / Decimal::from_atomics(3u128, 0).unwrap()
+fn ensure_not_paused(storage: &dyn Storage) -> Result<(), ContractError> {
+    if PAUSED.load(storage)? {
+        return Err(ContractError::Paused {});
+    }
+    Ok(())
 }
 
-fn query_oracle_prices(deps: Deps<TorramQueryWrapper>) -> StdResult<Binary> {
-    let request = QueryRequest::Custom(TorramQueryWrapper {
-        method: "get_all_contacts".to_string(),
-        args: json!({}),
-    });
-    deps.api.debug(&format!(
-        "QueryRequest::Custom payload = {}",
-        serde_json::to_string(&request).unwrap()
-    ));
-    let response: ContactsResponse = deps.querier.query(&request)?;
-    deps.api.debug(&format!("got contacts = {:?}", response));
-    if response.contacts.is_empty() {
-        return Err(StdError::generic_err("No oracle contacts found"));
-    }
-    let contact_str = &response.contacts[0].contact;
-
-    fn extract_price(s: &str, key: &str) -> Result<Decimal, StdError> {
-        s.split(',')
-            .find(|entry| entry.trim_start().starts_with(&format!("{key}:")))
-            .ok_or_else(|| StdError::generic_err(format!("{} not found in contact data", key)))?
-            .split(':')
-            .nth(1)
-            .ok_or_else(|| StdError::generic_err(format!("{} format invalid", key)))?
-            .trim()
-            .parse::<Decimal>()
-            .map_err(|e| StdError::generic_err(format!("{} parse error: {}", key, e)))
-    }
-
-    let price_data = PriceData {
-        btc: extract_price(contact_str, "BTC")?,
-        eth: extract_price(contact_str, "ETH")?,
-        usdc: extract_price(contact_str, "USDC")?,
-        usdt: extract_price(contact_str, "USDT")?,
-        dai: extract_price(contact_str, "DAI")?,
-    };
+fn try_propose_admin(
+    deps: DepsMut<TorramQuery>,
+    info: MessageInfo,
+    new_admin: String,
+) -> Result<Response, ContractError> {
+    ensure_admin(deps.storage, &info.sender)?;
+    let new_admin = deps.api.addr_validate(&new_admin)?;
+    PENDING_ADMIN.save(deps.storage, &new_admin)?;
+    Ok(Response::new()
+        .add_attribute("action", "propose_admin")
+        .add_attribute("pending_admin", new_admin))
+}
 
-    to_json_binary(&price_data)
+fn try_accept_admin(
+    deps: DepsMut<TorramQuery>,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    let pending = PENDING_ADMIN
+        .may_load(deps.storage)?
+        .ok_or(ContractError::NoPendingAdmin {})?;
+    if info.sender != pending {
+        return Err(ContractError::NotPendingAdmin {});
+    }
+    ADMIN.save(deps.storage, &pending)?;
+    PENDING_ADMIN.remove(deps.storage);
+    Ok(Response::new()
+        .add_attribute("action", "accept_admin")
+        .add_attribute("admin", pending))
 }
 
-#[cfg_attr(not(feature = "library"), entry_point)]
-pub fn query(deps: Deps<TorramQueryWrapper>, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
+fn try_update_pausers(
+    deps: DepsMut<TorramQuery>,
+    info: MessageInfo,
+    add: Vec<String>,
+    remove: Vec<String>,
+) -> Result<Response, ContractError> {
+    ensure_admin(deps.storage, &info.sender)?;
+    for addr in &add {
+        PAUSERS.save(deps.storage, &deps.api.addr_validate(addr)?, &Empty {})?;
+    }
+    for addr in &remove {
+        PAUSERS.remove(deps.storage, &deps.api.addr_validate(addr)?);
+    }
+    Ok(Response::new()
+        .add_attribute("action", "update_pausers")
+        .add_attribute("added", add.len().to_string())
+        .add_attribute("removed", remove.len().to_string()))
+}
+
+fn try_set_paused(
+    deps: DepsMut<TorramQuery>,
+    info: MessageInfo,
+    paused: bool,
+) -> Result<Response, ContractError> {
+    if !PAUSERS.has(deps.storage, &info.sender) {
+        return Err(ContractError::NotPauser {});
+    }
+    PAUSED.save(deps.storage, &paused)?;
+    let action = if paused { "pause" } else { "unpause" };
+    Ok(Response::new()
+        .add_attribute("action", action)
+        .add_attribute("sender", info.sender))
+}
+
+/// Upper-cases `symbol` and checks it is a plausible ticker.
+pub fn normalize_symbol(symbol: &str) -> Result<String, ContractError> {
+    let symbol = symbol.trim().to_ascii_uppercase();
+    if symbol.is_empty()
+        || symbol.len() > MAX_SYMBOL_LEN
+        || !symbol.chars().all(|c| c.is_ascii_alphanumeric())
+    {
+        return Err(ContractError::InvalidSymbol { symbol });
+    }
+    Ok(symbol)
+}
+
+fn register_asset(storage: &mut dyn Storage, asset: AssetInfo) -> Result<AssetInfo, ContractError> {
+    let symbol = normalize_symbol(&asset.symbol)?;
+    if ASSETS.has(storage, &symbol) {
+        return Err(ContractError::AssetAlreadyRegistered { symbol });
+    }
+    if asset.decimals > MAX_DECIMALS {
+        return Err(ContractError::InvalidDecimals {
+            decimals: asset.decimals,
+            max: MAX_DECIMALS,
+        });
+    }
+    validate_bounds(&symbol, &asset.bounds)?;
+    let asset = AssetInfo { symbol, ..asset };
+    ASSETS.save(storage, &asset.symbol, &asset)?;
+    PRICES.save(
+        storage,
+        &asset.symbol,
+        &StoredPrice {
+            price: Decimal::zero(),
+            last_updated_height: 0,
+            last_updated_time: Timestamp::from_nanos(0),
+        },
+    )?;
+    Ok(asset)
+}
+
+fn validate_bounds(symbol: &str, bounds: &PriceBounds) -> Result<(), ContractError> {
+    let inverted =
+        matches!((bounds.min_price, bounds.max_price), (Some(min), Some(max)) if min > max);
+    if inverted || bounds.max_deviation == Some(Decimal::zero()) {
+        return Err(ContractError::InvalidBounds {
+            symbol: symbol.to_string(),
+        });
+    }
+    Ok(())
+}
+
+fn load_asset(storage: &dyn Storage, symbol: &str) -> Result<AssetInfo, ContractError> {
+    let symbol = normalize_symbol(symbol)?;
+    ASSETS
+        .may_load(storage, &symbol)?
+        .ok_or(ContractError::UnknownAsset { symbol })
+}
+
+fn enabled_assets(storage: &dyn Storage) -> StdResult<Vec<AssetInfo>> {
+    ASSETS
+        .range(storage, None, None, Order::Ascending)
+        .filter(|item| item.as_ref().map_or(true, |(_, asset)| asset.enabled))
+        .map(|item| item.map(|(_, asset)| asset))
+        .collect()
+}
+
+fn try_register_asset(
+    deps: DepsMut<TorramQuery>,
+    info: MessageInfo,
+    asset: AssetInfo,
+) -> Result<Response, ContractError> {
+    ensure_admin(deps.storage, &info.sender)?;
+    let asset = register_asset(deps.storage, asset)?;
+    Ok(Response::new()
+        .add_attribute("action", "register_asset")
+        .add_attribute("symbol", asset.symbol))
+}
+
+fn try_update_asset(
+    deps: DepsMut<TorramQuery>,
+    info: MessageInfo,
+    symbol: String,
+    class: Option<AssetClass>,
+    decimals: Option<u8>,
+    enabled: Option<bool>,
+    bounds: Option<PriceBounds>,
+) -> Result<Response, ContractError> {
+    ensure_admin(deps.storage, &info.sender)?;
+    let mut asset = load_asset(deps.storage, &symbol)?;
+    if let Some(class) = class {
+        asset.class = class;
+    }
+    if let Some(decimals) = decimals {
+        if decimals > MAX_DECIMALS {
+            return Err(ContractError::InvalidDecimals {
+                decimals,
+                max: MAX_DECIMALS,
+            });
+        }
+        asset.decimals = decimals;
+    }
+    if let Some(enabled) = enabled {
+        asset.enabled = enabled;
+    }
+    if let Some(bounds) = bounds {
+        validate_bounds(&asset.symbol, &bounds)?;
+        asset.bounds = bounds;
+    }
+    ASSETS.save(deps.storage, &asset.symbol, &asset)?;
+    Ok(Response::new()
+        .add_attribute("action", "update_asset")
+        .add_attribute("symbol", asset.symbol)
+        .add_attribute("enabled", asset.enabled.to_string()))
+}
+
+fn try_update_config(
+    deps: DepsMut<TorramQuery>,
+    info: MessageInfo,
+    update: ConfigUpdate,
+) -> Result<Response, ContractError> {
+    ensure_admin(deps.storage, &info.sender)?;
+    let mut config = CONFIG.load(deps.storage)?;
+    if let Some(min_reporters) = update.min_reporters {
+        if min_reporters == 0 {
+            return Err(ContractError::InvalidMinReporters {});
+        }
+        config.min_reporters = min_reporters;
+    }
+    if let Some(keepers) = update.keepers {
+        config.keepers = keepers
+            .iter()
+            .map(|addr| deps.api.addr_validate(addr))
+            .collect::<StdResult<_>>()?;
+    }
+    if let Some(history_retention) = update.history_retention {
+        if history_retention == 0 {
+            return Err(ContractError::InvalidHistoryRetention {});
+        }
+        config.history_retention = history_retention;
+    }
+    if let Some(max_age) = update.max_age {
+        validate_max_age(&max_age)?;
+        config.max_age = max_age;
+    }
+    if let Some(quorum) = update.quorum {
+        ensure_quorum_reachable(deps.storage, quorum)?;
+        config.quorum = quorum;
+    }
+    if let Some(basket) = update.basket {
+        config.basket = validate_basket(basket)?;
+    }
+    if update.clear_depeg_threshold {
+        if update.depeg_threshold.is_some() {
+            return Err(ContractError::ConflictingDepegThreshold {});
+        }
+        config.depeg_threshold = None;
+    } else if update.depeg_threshold.is_some() {
+        validate_depeg_threshold(update.depeg_threshold)?;
+        config.depeg_threshold = update.depeg_threshold;
+    }
+    CONFIG.save(deps.storage, &config)?;
+    Ok(Response::new()
+        .add_attribute("action", "update_config")
+        .add_attribute("min_reporters", config.min_reporters.to_string())
+        .add_attribute("keepers", config.keepers.len().to_string())
+        .add_attribute("history_retention", config.history_retention.to_string())
+        .add_attribute("quorum", config.quorum.to_string()))
+}
+
+fn try_update_feeders(
+    deps: DepsMut<TorramQuery>,
+    info: MessageInfo,
+    add: Vec<String>,
+    remove: Vec<String>,
+) -> Result<Response, ContractError> {
+    ensure_admin(deps.storage, &info.sender)?;
+    for addr in &add {
+        let feeder = deps.api.addr_validate(addr)?;
+        if !FEEDERS.has(deps.storage, &feeder) {
+            FEEDERS.save(deps.storage, &feeder, &FeederStats::default())?;
+        }
+    }
+    for addr in &remove {
+        FEEDERS.remove(deps.storage, &deps.api.addr_validate(addr)?);
+    }
+    ensure_quorum_reachable(deps.storage, CONFIG.load(deps.storage)?.quorum)?;
+    Ok(Response::new()
+        .add_attribute("action", "update_feeders")
+        .add_attribute("added", add.len().to_string())
+        .add_attribute("removed", remove.len().to_string()))
+}
+
+/// A quorum larger than the feeder set would leave every round open forever.
+fn ensure_quorum_reachable(storage: &dyn Storage, quorum: u32) -> Result<(), ContractError> {
+    let feeders = FEEDERS.keys(storage, None, None, Order::Ascending).count() as u32;
+    if quorum == 0 || quorum > feeders {
+        return Err(ContractError::InvalidQuorum { quorum, feeders });
+    }
+    Ok(())
+}
+
+fn default_basket() -> Vec<BasketMember> {
+    DEFAULT_BASKET
+        .iter()
+        .map(|symbol| BasketMember {
+            symbol: symbol.to_string(),
+            weight: None,
+        })
+        .collect()
+}
+
+/// Normalizes member symbols and rejects duplicates and non-positive weights.
+fn validate_basket(basket: Vec<BasketMember>) -> Result<Vec<BasketMember>, ContractError> {
+    let mut validated: Vec<BasketMember> = Vec::with_capacity(basket.len());
+    for member in basket {
+        let symbol = normalize_symbol(&member.symbol)?;
+        if member.weight == Some(Decimal::zero()) || validated.iter().any(|m| m.symbol == symbol) {
+            return Err(ContractError::InvalidBasketMember { symbol });
+        }
+        validated.push(BasketMember { symbol, ..member });
+    }
+    Ok(validated)
+}
+
+fn validate_depeg_threshold(threshold: Option<Decimal>) -> Result<(), ContractError> {
+    if threshold == Some(Decimal::zero()) {
+        return Err(ContractError::InvalidDepegThreshold {});
+    }
+    Ok(())
+}
+
+fn validate_max_age(max_age: &[MaxAge]) -> Result<(), ContractError> {
+    for (i, entry) in max_age.iter().enumerate() {
+        if entry.seconds == 0 || max_age[..i].iter().any(|prev| prev.class == entry.class) {
+            return Err(ContractError::InvalidMaxAge {
+                class: entry.class.clone(),
+            });
+        }
+    }
+    Ok(())
+}
+
+fn try_update_prices(
+    deps: DepsMut<TorramQuery>,
+    env: Env,
+    info: MessageInfo,
+    prices: Vec<AssetPrice>,
+) -> Result<Response, ContractError> {
+    let round_id = CURRENT_ROUND.load(deps.storage)?;
+    let finalized = submit_round(deps.storage, &env, &info.sender, round_id, prices)?;
+    Ok(round_response(
+        "update_prices",
+        info.sender,
+        round_id,
+        finalized,
+    ))
+}
+
+fn try_update_prices_raw(
+    deps: DepsMut<TorramQuery>,
+    env: Env,
+    info: MessageInfo,
+    feed: String,
+    source_height: u64,
+) -> Result<Response, ContractError> {
+    if source_height > env.block.height {
+        return Err(ContractError::FutureSourceHeight {
+            source_height,
+            height: env.block.height,
+        });
+    }
+    let quoted = parse_prices(&feed)?;
+    let prices = recognised_prices(&enabled_assets(deps.storage)?, &quoted);
+    let ignored: Vec<String> = quoted
+        .into_keys()
+        .filter(|symbol| !prices.iter().any(|p| p.symbol == *symbol))
+        .collect();
+    let round_id = CURRENT_ROUND.load(deps.storage)?;
+    let finalized = submit_round(deps.storage, &env, &info.sender, round_id, prices)?;
+    Ok(
+        round_response("update_prices_raw", info.sender, round_id, finalized)
+            .add_attribute("source_height", source_height.to_string())
+            .add_attribute("ignored", ignored.join(",")),
+    )
+}
+
+fn try_submit_prices(
+    deps: DepsMut<TorramQuery>,
+    env: Env,
+    info: MessageInfo,
+    round_id: u64,
+    prices: Vec<AssetPrice>,
+) -> Result<Response, ContractError> {
+    let finalized = submit_round(deps.storage, &env, &info.sender, round_id, prices)?;
+    Ok(round_response(
+        "submit_prices",
+        info.sender,
+        round_id,
+        finalized,
+    ))
+}
+
+fn try_submit_signed_report(
+    deps: DepsMut<TorramQuery>,
+    env: Env,
+    info: MessageInfo,
+    report: PriceReport,
+    signature: Binary,
+) -> Result<Response, ContractError> {
+    let feeder = deps.api.addr_validate(&report.feeder)?;
+    let mut key = FEEDER_KEYS
+        .may_load(deps.storage, &feeder)?
+        .ok_or_else(|| ContractError::NoFeederKey {
+            feeder: feeder.to_string(),
+        })?;
+    if report.nonce <= key.last_nonce {
+        return Err(ContractError::NonceReused {
+            last: key.last_nonce,
+            got: report.nonce,
+        });
+    }
+    let now = env.block.time;
+    if report.timestamp > now || report.timestamp.plus_seconds(MAX_REPORT_AGE) < now {
+        return Err(ContractError::ReportExpired {
+            timestamp: report.timestamp,
+            max_age: MAX_REPORT_AGE,
+        });
+    }
+    let hash = Sha256::digest(report.sign_bytes(&env.block.chain_id, &env.contract.address)?);
+    if !deps.api.secp256k1_verify(&hash, &signature, &key.pubkey)? {
+        return Err(ContractError::InvalidSignature {});
+    }
+    key.last_nonce = report.nonce;
+    FEEDER_KEYS.save(deps.storage, &feeder, &key)?;
+
+    let round_id = report.round_id;
+    let finalized = submit_round(deps.storage, &env, &feeder, round_id, report.prices)?;
+    Ok(
+        round_response("submit_signed_report", feeder, round_id, finalized)
+            .add_attribute("relayer", info.sender)
+            .add_attribute("nonce", report.nonce.to_string()),
+    )
+}
+
+fn try_set_feeder_key(
+    deps: DepsMut<TorramQuery>,
+    info: MessageInfo,
+    pubkey: Binary,
+) -> Result<Response, ContractError> {
+    if !FEEDERS.has(deps.storage, &info.sender) {
+        return Err(ContractError::NotFeeder {});
+    }
+    match (pubkey.len(), pubkey.first()) {
+        (33, Some(0x02 | 0x03)) | (65, Some(0x04)) => {}
+        _ => return Err(ContractError::InvalidPubkey {}),
+    }
+    // Rotating the key keeps the nonce, so reports signed before stay spent.
+    let last_nonce = FEEDER_KEYS
+        .may_load(deps.storage, &info.sender)?
+        .map_or(0, |key| key.last_nonce);
+    FEEDER_KEYS.save(
+        deps.storage,
+        &info.sender,
+        &FeederKey { pubkey, last_nonce },
+    )?;
+    Ok(Response::new()
+        .add_attribute("action", "set_feeder_key")
+        .add_attribute("feeder", info.sender))
+}
+
+fn round_response(
+    action: &str,
+    sender: Addr,
+    round_id: u64,
+    finalized: Option<Response>,
+) -> Response {
+    let is_finalized = finalized.is_some();
+    finalized
+        .unwrap_or_default()
+        .add_attribute("action", action)
+        .add_attribute("sender", sender)
+        .add_attribute("round_id", round_id.to_string())
+        .add_attribute("finalized", is_finalized.to_string())
+}
+
+/// Records a feeder's prices for the open round and finalizes the round once
+/// `quorum` feeders have submitted.
+///
+/// On finalization every asset quoted by at least `quorum` feeders is taken at
+/// the median of its submissions and screened against its bounds; the prices
+/// that pass are stored. Returns the events and subscriber callbacks
+/// announcing the outcome.
+fn submit_round(
+    storage: &mut dyn Storage,
+    env: &Env,
+    feeder: &Addr,
+    round_id: u64,
+    prices: Vec<AssetPrice>,
+) -> Result<Option<Response>, ContractError> {
+    ensure_not_paused(storage)?;
+    let mut stats = FEEDERS
+        .may_load(storage, feeder)?
+        .ok_or(ContractError::NotFeeder {})?;
+    let current = CURRENT_ROUND.load(storage)?;
+    if round_id != current {
+        return Err(ContractError::WrongRound {
+            expected: current,
+            got: round_id,
+        });
+    }
+    let prices = validate_prices(storage, prices)?;
+
+    let mut round = ROUNDS.may_load(storage, round_id)?.unwrap_or(Round {
+        round_id,
+        submissions: vec![],
+        finalized_height: None,
+        prices: vec![],
+    });
+    if round.submissions.iter().any(|s| s.feeder == *feeder) {
+        return Err(ContractError::AlreadySubmitted { round_id });
+    }
+    round.submissions.push(Submission {
+        feeder: feeder.clone(),
+        prices,
+    });
+    stats.submissions += 1;
+    stats.last_round = round_id;
+    stats.last_submission_height = env.block.height;
+    FEEDERS.save(storage, feeder, &stats)?;
+
+    let quorum = CONFIG.load(storage)?.quorum;
+    if (round.submissions.len() as u32) < quorum {
+        ROUNDS.save(storage, round_id, &round)?;
+        return Ok(None);
+    }
+
+    let mut quotes: BTreeMap<&str, Vec<Decimal>> = BTreeMap::new();
+    for submission in &round.submissions {
+        for p in &submission.prices {
+            quotes.entry(&p.symbol).or_default().push(p.price);
+        }
+    }
+    let medians: Vec<AssetPrice> = quotes
+        .into_iter()
+        .filter(|(_, values)| values.len() as u32 >= quorum)
+        .map(|(symbol, mut values)| AssetPrice {
+            symbol: symbol.to_string(),
+            price: median(&mut values),
+        })
+        .collect();
+    let submitters: Vec<Addr> = round.submissions.iter().map(|s| s.feeder.clone()).collect();
+    let Screened { accepted, rejected } = screen_prices(storage, env, medians, &submitters)?;
+    let changes = store_prices(storage, env, &accepted)?;
+
+    for submission in &round.submissions {
+        if let Some(mut stats) = FEEDERS.may_load(storage, &submission.feeder)? {
+            stats.finalized_rounds += 1;
+            FEEDERS.save(storage, &submission.feeder, &stats)?;
+        }
+    }
+    round.finalized_height = Some(env.block.height);
+    round.prices = accepted;
+    ROUNDS.save(storage, round_id, &round)?;
+    ROUNDS.remove(storage, round_id - 1);
+    CURRENT_ROUND.save(storage, &(round_id + 1))?;
+    let response = Response::new().add_events(rejected);
+    let response = publish_prices(response, storage, env, &submitters, &changes)?;
+    Ok(Some(response))
+}
+
+fn try_sync_from_oracle(
+    deps: DepsMut<TorramQuery>,
+    env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    ensure_not_paused(deps.storage)?;
+    let config = CONFIG.load(deps.storage)?;
+    if !config.keepers.is_empty() && !config.keepers.contains(&info.sender) {
+        return Err(ContractError::NotKeeper {});
+    }
+    // Assets that too few feeders quote are skipped so they do not hold back the rest.
+    let fetched = fetch_oracle_prices(deps.storage, deps.api, &deps.querier)?;
+    let skipped = fetched
+        .under_quoted
+        .iter()
+        .map(|(symbol, _)| symbol.as_str())
+        .collect::<Vec<_>>()
+        .join(",");
+    let updated = validate_prices(deps.storage, fetched.oracle.prices)?;
+    let Screened { accepted, rejected } = screen_prices(
+        deps.storage,
+        &env,
+        updated,
+        std::slice::from_ref(&info.sender),
+    )?;
+    let changes = store_prices(deps.storage, &env, &accepted)?;
+
+    let response = Response::new()
+        .add_attribute("action", "sync_from_oracle")
+        .add_attribute("sender", &info.sender)
+        .add_attribute("reporters", fetched.oracle.reporters.len().to_string())
+        .add_attribute("skipped", skipped)
+        .add_events(rejected);
+    Ok(publish_prices(
+        response,
+        deps.storage,
+        &env,
+        std::slice::from_ref(&info.sender),
+        &changes,
+    )?)
+}
+
+fn try_force_update(
+    deps: DepsMut<TorramQuery>,
+    env: Env,
+    info: MessageInfo,
+    symbol: String,
+) -> Result<Response, ContractError> {
+    ensure_not_paused(deps.storage)?;
+    let asset = load_asset(deps.storage, &symbol)?;
+    let pending = PENDING_PRICES
+        .may_load(deps.storage, &asset.symbol)?
+        .ok_or(ContractError::NoPendingPrice {
+            symbol: asset.symbol.clone(),
+        })?;
+    if info.sender != ADMIN.load(deps.storage)? {
+        if !FEEDERS.has(deps.storage, &info.sender) {
+            return Err(ContractError::NotFeeder {});
+        }
+        if pending.proposed_by.contains(&info.sender) {
+            return Err(ContractError::CannotConfirmOwnPrice {});
+        }
+    }
+    let confirmed = [AssetPrice {
+        symbol: asset.symbol,
+        price: pending.price,
+    }];
+    let changes = store_prices(deps.storage, &env, &confirmed)?;
+    PENDING_PRICES.remove(deps.storage, &pending.symbol);
+
+    let response = Response::new()
+        .add_attribute("action", "force_update")
+        .add_attribute("sender", &info.sender)
+        .add_attribute("symbol", pending.symbol);
+    Ok(publish_prices(
+        response,
+        deps.storage,
+        &env,
+        std::slice::from_ref(&info.sender),
+        &changes,
+    )?)
+}
+
+fn try_subscribe(
+    deps: DepsMut<TorramQuery>,
+    info: MessageInfo,
+    symbols: Vec<String>,
+    deviation_bps: u32,
+) -> Result<Response, ContractError> {
+    if symbols.is_empty() {
+        return Err(ContractError::NoSymbols {});
+    }
+    if deviation_bps == 0 || deviation_bps > 10_000 {
+        return Err(ContractError::InvalidDeviationBps {});
+    }
+    if !SUBSCRIPTIONS.has(deps.storage, &info.sender) {
+        let count = SUBSCRIPTIONS
+            .keys(deps.storage, None, None, Order::Ascending)
+            .count() as u32;
+        if count >= MAX_SUBSCRIPTIONS {
+            return Err(ContractError::TooManySubscriptions {
+                max: MAX_SUBSCRIPTIONS,
+            });
+        }
+    }
+    let mut subscription = Subscription {
+        symbols: vec![],
+        deviation_bps,
+        notified: vec![],
+        failures: 0,
+    };
+    for symbol in symbols {
+        let asset = load_asset(deps.storage, &symbol)?;
+        if subscription.symbols.contains(&asset.symbol) {
+            continue;
+        }
+        let stored = PRICES.load(deps.storage, &asset.symbol)?;
+        if !stored.is_placeholder() {
+            subscription.notified.push(AssetPrice {
+                symbol: asset.symbol.clone(),
+                price: stored.price,
+            });
+        }
+        subscription.symbols.push(asset.symbol);
+    }
+    SUBSCRIPTIONS.save(deps.storage, &info.sender, &subscription)?;
+    Ok(Response::new()
+        .add_attribute("action", "subscribe")
+        .add_attribute("subscriber", info.sender)
+        .add_attribute("symbols", subscription.symbols.join(",")))
+}
+
+/// Prices split by `screen_prices`, with a `price_rejected` event per rejection.
+struct Screened {
+    accepted: Vec<AssetPrice>,
+    rejected: Vec<Event>,
+}
+
+/// Checks new prices against each asset's `PriceBounds`.
+///
+/// Prices outside `[min_price, max_price]` are dropped. Prices that moved more
+/// than `max_deviation` from the stored price are saved to `PENDING_PRICES`
+/// for `ForceUpdate`. An accepted price discards any pending one.
+fn screen_prices(
+    storage: &mut dyn Storage,
+    env: &Env,
+    prices: Vec<AssetPrice>,
+    proposed_by: &[Addr],
+) -> Result<Screened, ContractError> {
+    let mut screened = Screened {
+        accepted: vec![],
+        rejected: vec![],
+    };
+    for p in prices {
+        let bounds = load_asset(storage, &p.symbol)?.bounds;
+        let rejected = Event::new("price_rejected")
+            .add_attribute("symbol", &p.symbol)
+            .add_attribute("price", p.price.to_string());
+
+        let below = bounds.min_price.filter(|min| p.price < *min);
+        let above = bounds.max_price.filter(|max| p.price > *max);
+        if let Some(limit) = below.or(above) {
+            screened.rejected.push(
+                rejected
+                    .add_attribute("reason", "out_of_bounds")
+                    .add_attribute("limit", limit.to_string()),
+            );
+            continue;
+        }
+
+        let stored = PRICES.load(storage, &p.symbol)?;
+        if let (Some(max_deviation), false) = (bounds.max_deviation, stored.is_placeholder()) {
+            // A deviation too large for a Decimal exceeds any limit
+            let deviation = p
+                .price
+                .abs_diff(stored.price)
+                .checked_div(stored.price)
+                .ok();
+            if deviation.is_none_or(|deviation| deviation > max_deviation) {
+                PENDING_PRICES.save(
+                    storage,
+                    &p.symbol,
+                    &PendingPrice {
+                        symbol: p.symbol.clone(),
+                        price: p.price,
+                        previous: stored.price,
+                        proposed_by: proposed_by.to_vec(),
+                        height: env.block.height,
+                    },
+                )?;
+                screened.rejected.push(
+                    rejected
+                        .add_attribute("reason", "deviation")
+                        .add_attribute("previous", stored.price.to_string())
+                        .add_attribute(
+                            "deviation",
+                            deviation.map_or("overflow".to_string(), |d| d.to_string()),
+                        )
+                        .add_attribute("max_deviation", max_deviation.to_string()),
+                );
+                continue;
+            }
+        }
+        PENDING_PRICES.remove(storage, &p.symbol);
+        screened.accepted.push(p);
+    }
+    Ok(screened)
+}
+
+/// Checks every price is positive and targets a distinct, enabled asset.
+///
+/// Returns the prices with their symbols normalized.
+fn validate_prices(
+    storage: &dyn Storage,
+    prices: Vec<AssetPrice>,
+) -> Result<Vec<AssetPrice>, ContractError> {
+    if prices.is_empty() {
+        return Err(ContractError::EmptyPrices {});
+    }
+
+    let mut updated: Vec<AssetPrice> = Vec::with_capacity(prices.len());
+    for AssetPrice { symbol, price } in prices {
+        let asset = load_asset(storage, &symbol)?;
+        if !asset.enabled {
+            return Err(ContractError::AssetDisabled {
+                symbol: asset.symbol,
+            });
+        }
+        if updated.iter().any(|p| p.symbol == asset.symbol) {
+            return Err(ContractError::DuplicatePrice {
+                symbol: asset.symbol,
+            });
+        }
+        if price.is_zero() {
+            return Err(ContractError::ZeroPrice {
+                symbol: asset.symbol,
+            });
+        }
+        updated.push(AssetPrice {
+            symbol: asset.symbol,
+            price,
+        });
+    }
+    Ok(updated)
+}
+
+/// A stored price and the one it replaced.
+struct PriceChange {
+    symbol: String,
+    price: Decimal,
+    /// `None` when the asset only held its registration placeholder.
+    previous: Option<Decimal>,
+}
+
+/// Stores validated prices, advancing each asset's TWAP accumulator and
+/// recording it in the asset's history.
+fn store_prices(
+    storage: &mut dyn Storage,
+    env: &Env,
+    prices: &[AssetPrice],
+) -> Result<Vec<PriceChange>, ContractError> {
+    let retention = CONFIG.load(storage)?.history_retention;
+    let mut changes = Vec::with_capacity(prices.len());
+    for AssetPrice { symbol, price } in prices {
+        let previous = PRICES
+            .may_load(storage, symbol)?
+            .filter(|stored| !stored.is_placeholder())
+            .map(|stored| stored.price);
+        changes.push(PriceChange {
+            symbol: symbol.clone(),
+            price: *price,
+            previous,
+        });
+        PRICES.save(
+            storage,
+            symbol,
+            &StoredPrice {
+                price: *price,
+                last_updated_height: env.block.height,
+                last_updated_time: env.block.time,
+            },
+        )?;
+        let cumulative = accumulate(storage, env, symbol, *price)?;
+        record_history(storage, env, symbol, *price, cumulative, retention)?;
+    }
+    Ok(changes)
+}
+
+/// `acc.cumulative` extended by the time `acc.price` has been in effect until `time`.
+fn cumulative_at(acc: &Accumulator, time: Timestamp) -> Decimal256 {
+    let elapsed_nanos = time.nanos().saturating_sub(acc.time.nanos());
+    acc.cumulative
+        + Decimal256::from(acc.price) * Decimal256::from_ratio(elapsed_nanos, NANOS_PER_SECOND)
+}
+
+/// Advances the accumulator of `symbol` to the current block and makes `price`
+/// the price in effect. Returns the cumulative value before `price` applies.
+fn accumulate(
+    storage: &mut dyn Storage,
+    env: &Env,
+    symbol: &str,
+    price: Decimal,
+) -> StdResult<Decimal256> {
+    let cumulative = ACCUMULATORS
+        .may_load(storage, symbol)?
+        .map(|acc| cumulative_at(&acc, env.block.time))
+        .unwrap_or_default();
+    ACCUMULATORS.save(
+        storage,
+        symbol,
+        &Accumulator {
+            cumulative,
+            price,
+            time: env.block.time,
+        },
+    )?;
+    Ok(cumulative)
+}
+
+/// Appends `price` to the history of `symbol` and prunes the oldest points
+/// beyond `retention`. A second update in the same block replaces the first.
+fn record_history(
+    storage: &mut dyn Storage,
+    env: &Env,
+    symbol: &str,
+    price: Decimal,
+    cumulative: Decimal256,
+    retention: u32,
+) -> StdResult<()> {
+    let key = (symbol, env.block.height);
+    let mut len = HISTORY_LEN.may_load(storage, symbol)?.unwrap_or_default();
+    if !PRICE_HISTORY.has(storage, key) {
+        len += 1;
+    }
+    PRICE_HISTORY.save(
+        storage,
+        key,
+        &PricePoint {
+            height: env.block.height,
+            time: env.block.time,
+            price,
+            cumulative,
+        },
+    )?;
+
+    if len > retention {
+        let expired: Vec<u64> = PRICE_HISTORY
+            .prefix(symbol)
+            .keys(storage, None, None, Order::Ascending)
+            .take((len - retention) as usize)
+            .collect::<StdResult<_>>()?;
+        for height in expired {
+            PRICE_HISTORY.remove(storage, (symbol, height));
+        }
+        len = retention;
+    }
+    HISTORY_LEN.save(storage, symbol, &len)
+}
+
+/// Events for freshly stored prices.
+///
+/// The `oracle_prices` event carries `version`, `height` and the comma-separated
+/// `feeder`s, then for every updated symbol `<symbol>` (the new price),
+/// `<symbol>_previous` and `<symbol>_change_pct` (both omitted for a first
+/// price), and finally the basket `average`. Symbols are lower-cased and never
+/// contain `_`, so keys cannot collide. A `stablecoin_depeg` event follows for
+/// every basket member further than `depeg_threshold` from 1.0.
+fn price_events(
+    storage: &dyn Storage,
+    env: &Env,
+    feeders: &[Addr],
+    changes: &[PriceChange],
+) -> StdResult<Vec<Event>> {
+    if changes.is_empty() {
+        return Ok(vec![]);
+    }
+    let config = CONFIG.load(storage)?;
+    let feeders: Vec<&str> = feeders.iter().map(Addr::as_str).collect();
+    let mut event = Event::new("oracle_prices")
+        .add_attribute("version", PRICES_EVENT_VERSION)
+        .add_attribute("height", env.block.height.to_string())
+        .add_attribute("feeder", feeders.join(","));
+    let mut depegs = vec![];
+    for p in changes {
+        let key = p.symbol.to_ascii_lowercase();
+        event = event.add_attribute(&key, p.price.to_string());
+        if let Some(previous) = p.previous {
+            event = event
+                .add_attribute(format!("{key}_previous"), previous.to_string())
+                .add_attribute(format!("{key}_change_pct"), change_pct(previous, p.price));
+        }
+        if !config.basket.iter().any(|m| m.symbol == p.symbol) {
+            continue;
+        }
+        let deviation = p.price.abs_diff(Decimal::one());
+        if let Some(threshold) = config.depeg_threshold.filter(|t| deviation > *t) {
+            depegs.push(
+                Event::new("stablecoin_depeg")
+                    .add_attribute("symbol", &p.symbol)
+                    .add_attribute("price", p.price.to_string())
+                    .add_attribute("deviation", deviation.to_string())
+                    .add_attribute("threshold", threshold.to_string()),
+            );
+        }
+    }
+    let members = basket_members(storage, &config.basket)?;
+    if !members.is_empty() {
+        // An average too large to compute must not fail the update
+        let average = basket_average(&members).map_or("overflow".to_string(), |a| a.to_string());
+        event = event.add_attribute("average", average);
+    }
+    Ok([vec![event], depegs].concat())
+}
+
+/// Adds the `price_events` of freshly stored prices to `response`, plus a
+/// callback to every subscriber with a symbol that crossed its threshold.
+fn publish_prices(
+    response: Response,
+    storage: &mut dyn Storage,
+    env: &Env,
+    feeders: &[Addr],
+    changes: &[PriceChange],
+) -> StdResult<Response> {
+    let events = price_events(storage, env, feeders, changes)?;
+    let callbacks = notify_subscribers(storage, env, changes)?;
+    Ok(response.add_events(events).add_submessages(callbacks))
+}
+
+/// Builds the `SubscriberMsg::OraclePriceUpdate` callbacks for `changes` and
+/// records the pushed prices as each subscriber's new reference.
+///
+/// Callbacks are sent with `reply_on_error` and a gas limit; `reply` only
+/// counts the failure, so a broken subscriber cannot revert the update.
+fn notify_subscribers(
+    storage: &mut dyn Storage,
+    env: &Env,
+    changes: &[PriceChange],
+) -> StdResult<Vec<SubMsg>> {
+    if changes.is_empty() {
+        return Ok(vec![]);
+    }
+    let subscriptions = SUBSCRIPTIONS
+        .range(storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    let mut callbacks = vec![];
+    for (subscriber, mut subscription) in subscriptions {
+        let threshold = Decimal::from_ratio(subscription.deviation_bps, 10_000u32);
+        let mut prices = vec![];
+        for change in changes {
+            if !subscription.symbols.contains(&change.symbol) {
+                continue;
+            }
+            let notified = subscription
+                .notified
+                .iter_mut()
+                .find(|p| p.symbol == change.symbol);
+            // A threshold too large to compute counts as crossed
+            let below_threshold = |p: &AssetPrice| {
+                p.price
+                    .checked_mul(threshold)
+                    .is_ok_and(|min_move| change.price.abs_diff(p.price) < min_move)
+            };
+            match notified {
+                Some(p) if below_threshold(p) => continue,
+                Some(p) => p.price = change.price,
+                None => subscription.notified.push(AssetPrice {
+                    symbol: change.symbol.clone(),
+                    price: change.price,
+                }),
+            }
+            prices.push(PriceResponse {
+                symbol: change.symbol.clone(),
+                price: change.price,
+                last_updated_height: env.block.height,
+                last_updated_time: env.block.time,
+            });
+        }
+        if prices.is_empty() {
+            continue;
+        }
+        SUBSCRIPTIONS.save(storage, &subscriber, &subscription)?;
+        let msg = WasmMsg::Execute {
+            contract_addr: subscriber.to_string(),
+            msg: to_json_binary(&SubscriberMsg::OraclePriceUpdate { prices })?,
+            funds: vec![],
+        };
+        callbacks.push(
+            SubMsg::reply_on_error(msg, SUBSCRIBER_REPLY_ID)
+                .with_gas_limit(SUBSCRIBER_GAS_LIMIT)
+                .with_payload(to_json_binary(&subscriber)?),
+        );
+    }
+    Ok(callbacks)
+}
+
+/// Signed percentage change from `previous` (which is never zero) to `price`,
+/// or `overflow` if it does not fit in a `Decimal`.
+fn change_pct(previous: Decimal, price: Decimal) -> String {
+    let pct = price
+        .abs_diff(previous)
+        .checked_div(previous)
+        .ok()
+        .and_then(|ratio| ratio.checked_mul(Decimal::percent(10_000)).ok());
+    match pct {
+        Some(pct) if price < previous => format!("-{pct}"),
+        Some(pct) => pct.to_string(),
+        None => "overflow".to_string(),
+    }
+}
+
+/// Weighted average of the basket members that have a price.
+fn basket_price(
+    storage: &dyn Storage,
+    basket: &[BasketMember],
+) -> Result<BasketPriceResponse, ContractError> {
+    let members = basket_members(storage, basket)?;
+    if members.is_empty() {
+        return Err(ContractError::EmptyBasket {});
+    }
+    let price = basket_average(&members).ok_or(ContractError::BasketOverflow {})?;
+    Ok(BasketPriceResponse { price, members })
+}
+
+/// The basket members that have a price, with their weights.
+fn basket_members(
+    storage: &dyn Storage,
+    basket: &[BasketMember],
+) -> StdResult<Vec<BasketMemberPrice>> {
+    let mut members = vec![];
+    for member in basket {
+        let Some(stored) = PRICES.may_load(storage, &member.symbol)? else {
+            continue;
+        };
+        if stored.is_placeholder() {
+            continue;
+        }
+        members.push(BasketMemberPrice {
+            symbol: member.symbol.clone(),
+            price: stored.price,
+            weight: member.weight.unwrap_or(Decimal::one()),
+        });
+    }
+    Ok(members)
+}
+
+/// Weighted average of `members`, or `None` if it overflows a `Decimal`.
+fn basket_average(members: &[BasketMemberPrice]) -> Option<Decimal> {
+    let mut total_weight = Decimal::zero();
+    let mut weighted = Decimal::zero();
+    for m in members {
+        total_weight = total_weight.checked_add(m.weight).ok()?;
+        weighted = weighted
+            .checked_add(m.price.checked_mul(m.weight).ok()?)
+            .ok()?;
+    }
+    weighted.checked_div(total_weight).ok()
+}
+
+/// The prices `quoted` for `assets`, in asset order; other symbols are ignored.
+fn recognised_prices(assets: &[AssetInfo], quoted: &BTreeMap<String, Decimal>) -> Vec<AssetPrice> {
+    assets
+        .iter()
+        .filter_map(|asset| {
+            quoted.get(&asset.symbol).map(|&price| AssetPrice {
+                symbol: asset.symbol.clone(),
+                price,
+            })
+        })
+        .collect()
+}
+
+/// Middle value of `values`, or the mean of the two middle values for an even count.
+fn median(values: &mut [Decimal]) -> Decimal {
+    values.sort();
+    let mid = values.len() / 2;
+    if values.len() % 2 == 1 {
+        values[mid]
+    } else {
+        (values[mid - 1] + values[mid]) / Uint128::new(2)
+    }
+}
+
+/// Oracle prices of the enabled assets that enough reporters quote.
+struct FetchedPrices {
+    oracle: OraclePrices,
+    /// Enabled assets quoted by fewer than `min_reporters`, with their quote count.
+    under_quoted: Vec<(String, u32)>,
+    min_reporters: u32,
+}
+
+impl FetchedPrices {
+    /// The prices, or `InsufficientReporters` for the first under-quoted asset.
+    fn strict(self) -> Result<OraclePrices, ContractError> {
+        match self.under_quoted.into_iter().next() {
+            Some((symbol, got)) => Err(ContractError::InsufficientReporters {
+                symbol,
+                got,
+                need: self.min_reporters,
+            }),
+            None => Ok(self.oracle),
+        }
+    }
+}
+
+/// Reads every `tssconsensus` contact and aggregates the enabled assets by median.
+///
+/// A reporter that is missing an asset is left out of that asset's median, and
+/// one whose contact fails to parse is left out entirely. Only the first
+/// contact per address is counted. Assets quoted by fewer than `min_reporters`
+/// are left out of the prices and listed in `under_quoted`.
+fn fetch_oracle_prices(
+    storage: &dyn Storage,
+    api: &dyn Api,
+    querier: &QuerierWrapper<TorramQuery>,
+) -> Result<FetchedPrices, ContractError> {
+    let response = querier.all_contacts()?;
+    api.debug(&format!("got contacts = {:?}", response));
+    if response.contacts.is_empty() {
+        return Err(ContractError::NoOracleContacts {});
+    }
+
+    let assets = enabled_assets(storage)?;
+    let mut reporters: BTreeMap<String, Vec<AssetPrice>> = BTreeMap::new();
+    let mut seen = BTreeSet::new();
+    for contact in response.contacts {
+        if !seen.insert(contact.address.clone()) {
+            continue;
+        }
+        let quoted = match parse_prices(&contact.contact) {
+            Ok(quoted) => quoted,
+            Err(err) => {
+                api.debug(&format!("skipping contact of {}: {}", contact.address, err));
+                continue;
+            }
+        };
+        reporters.insert(contact.address, recognised_prices(&assets, &quoted));
+    }
+
+    let min_reporters = CONFIG.load(storage)?.min_reporters;
+    let mut prices = vec![];
+    let mut under_quoted = vec![];
+    for asset in assets {
+        let mut values: Vec<Decimal> = reporters
+            .values()
+            .filter_map(|prices| prices.iter().find(|p| p.symbol == asset.symbol))
+            .map(|p| p.price)
+            .collect();
+        let got = values.len() as u32;
+        if got == 0 || got < min_reporters {
+            under_quoted.push((asset.symbol, got));
+            continue;
+        }
+        prices.push(AssetPrice {
+            price: median(&mut values),
+            symbol: asset.symbol,
+        });
+    }
+
+    Ok(FetchedPrices {
+        oracle: OraclePrices { prices, reporters },
+        under_quoted,
+        min_reporters,
+    })
+}
+
+fn query_prices(deps: Deps<TorramQuery>) -> StdResult<Binary> {
+    let prices = enabled_assets(deps.storage)?
+        .into_iter()
+        .map(|asset| {
+            Ok(AssetPrice {
+                price: PRICES.load(deps.storage, &asset.symbol)?.price,
+                symbol: asset.symbol,
+            })
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+    to_json_binary(&PriceData { prices })
+}
+
+fn price_response(symbol: String, stored: StoredPrice) -> PriceResponse {
+    PriceResponse {
+        symbol,
+        price: stored.price,
+        last_updated_height: stored.last_updated_height,
+        last_updated_time: stored.last_updated_time,
+    }
+}
+
+fn query_price(deps: Deps<TorramQuery>, symbol: String) -> Result<Binary, ContractError> {
+    let asset = load_asset(deps.storage, &symbol)?;
+    let stored = PRICES.load(deps.storage, &asset.symbol)?;
+    Ok(to_json_binary(&price_response(asset.symbol, stored))?)
+}
+
+fn query_prices_for(
+    deps: Deps<TorramQuery>,
+    env: Env,
+    symbols: Vec<String>,
+) -> Result<Binary, ContractError> {
+    if symbols.len() > MAX_LIMIT as usize {
+        return Err(ContractError::TooManySymbols { max: MAX_LIMIT });
+    }
+    let prices = symbols
+        .into_iter()
+        .map(
+            |symbol| match load_checked_price(deps.storage, &env, &symbol) {
+                Ok(price) => PriceResult {
+                    symbol: price.symbol.clone(),
+                    price: Some(price),
+                    error: None,
+                },
+                Err(err) => PriceResult {
+                    symbol,
+                    price: None,
+                    error: Some(err.to_string()),
+                },
+            },
+        )
+        .collect();
+    Ok(to_json_binary(&PricesForResponse { prices })?)
+}
+
+fn query_assets(
+    deps: Deps<TorramQuery>,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> Result<Binary, ContractError> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(|s| normalize_symbol(&s)).transpose()?;
+    let assets = ASSETS
+        .range(
+            deps.storage,
+            start.as_deref().map(Bound::exclusive),
+            None,
+            Order::Ascending,
+        )
+        .take(limit)
+        .map(|item| item.map(|(_, asset)| asset))
+        .collect::<StdResult<Vec<_>>>()?;
+    Ok(to_json_binary(&AssetsResponse { assets })?)
+}
+
+/// Loads the price of `symbol`, failing if it is unusable at the current block:
+/// the contract is paused, or the asset is disabled, never set, or older than
+/// its class's `max_age`.
+fn load_checked_price(
+    storage: &dyn Storage,
+    env: &Env,
+    symbol: &str,
+) -> Result<PriceResponse, ContractError> {
+    ensure_not_paused(storage)?;
+    let asset = load_asset(storage, symbol)?;
+    if !asset.enabled {
+        return Err(ContractError::AssetDisabled {
+            symbol: asset.symbol,
+        });
+    }
+    let stored = PRICES.load(storage, &asset.symbol)?;
+    if stored.is_placeholder() {
+        return Err(ContractError::PriceNotSet {
+            symbol: asset.symbol,
+        });
+    }
+    let max_age = CONFIG
+        .load(storage)?
+        .max_age
+        .into_iter()
+        .find(|entry| entry.class == asset.class);
+    if let Some(MaxAge { seconds, .. }) = max_age {
+        let age_seconds = env
+            .block
+            .time
+            .seconds()
+            .saturating_sub(stored.last_updated_time.seconds());
+        if age_seconds > seconds {
+            return Err(ContractError::StalePrice {
+                symbol: asset.symbol,
+                age_seconds,
+                max_age: seconds,
+            });
+        }
+    }
+    Ok(price_response(asset.symbol, stored))
+}
+
+/// Divides the checked price of `base` by that of `quote`.
+///
+/// Both legs go through `load_checked_price`, so a leg that is unknown,
+/// disabled, never set (or zero) or stale fails the query. The quotient is
+/// computed with `Decimal`'s 18 places and then rounded down to the quote
+/// asset's `decimals`.
+fn query_cross_rate(
+    deps: Deps<TorramQuery>,
+    env: Env,
+    base: String,
+    quote: String,
+) -> Result<Binary, ContractError> {
+    let base_price = load_checked_price(deps.storage, &env, &base)?;
+    let quote_price = load_checked_price(deps.storage, &env, &quote)?;
+    let decimals = load_asset(deps.storage, &quote_price.symbol)?.decimals;
+    let overflow = || ContractError::CrossRateOverflow {
+        base: base_price.symbol.clone(),
+        quote: quote_price.symbol.clone(),
+    };
+
+    let rate = base_price
+        .price
+        .checked_div(quote_price.price)
+        .map_err(|_| overflow())?;
+    let scale = Uint128::from(10u128.pow(u32::from(MAX_DECIMALS - decimals)));
+    let rate = Decimal::from_atomics(rate.atomics() / scale, u32::from(decimals))
+        .map_err(|_| overflow())?;
+    Ok(to_json_binary(&CrossRateResponse {
+        base: base_price.symbol,
+        quote: quote_price.symbol,
+        rate,
+        decimals,
+        last_updated_time: base_price
+            .last_updated_time
+            .min(quote_price.last_updated_time),
+    })?)
+}
+
+fn query_price_at(
+    deps: Deps<TorramQuery>,
+    symbol: String,
+    height: u64,
+) -> Result<Binary, ContractError> {
+    let asset = load_asset(deps.storage, &symbol)?;
+    let point = PRICE_HISTORY
+        .prefix(&asset.symbol)
+        .range(
+            deps.storage,
+            None,
+            Some(Bound::inclusive(height)),
+            Order::Descending,
+        )
+        .next()
+        .transpose()?
+        .map(|(_, point)| point)
+        .ok_or(ContractError::NoPriceAt {
+            symbol: asset.symbol,
+            height,
+        })?;
+    Ok(to_json_binary(&point)?)
+}
+
+fn query_price_history(
+    deps: Deps<TorramQuery>,
+    symbol: String,
+    start: Option<u64>,
+    end: Option<u64>,
+    limit: Option<u32>,
+) -> Result<Binary, ContractError> {
+    let asset = load_asset(deps.storage, &symbol)?;
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let points = PRICE_HISTORY
+        .prefix(&asset.symbol)
+        .range(
+            deps.storage,
+            start.map(Bound::inclusive),
+            end.map(Bound::inclusive),
+            Order::Ascending,
+        )
+        .take(limit)
+        .map(|item| item.map(|(_, point)| point))
+        .collect::<StdResult<Vec<_>>>()?;
+    Ok(to_json_binary(&PriceHistoryResponse {
+        symbol: asset.symbol,
+        points,
+    })?)
+}
+
+/// Averages the price of `symbol` over `[now - window_seconds, now]`.
+///
+/// The accumulator value at the start of the window is interpolated from the
+/// newest history point at or before it, so the window must not reach past the
+/// retained history. Cost grows with the number of points inside the window.
+fn query_twap(
+    deps: Deps<TorramQuery>,
+    env: Env,
+    symbol: String,
+    window_seconds: u64,
+) -> Result<Binary, ContractError> {
+    if window_seconds == 0 {
+        return Err(ContractError::InvalidTwapWindow {});
+    }
+    let asset = load_asset(deps.storage, &symbol)?;
+    let insufficient = || ContractError::InsufficientHistory {
+        symbol: asset.symbol.clone(),
+        window_seconds,
+    };
+
+    let now = env.block.time;
+    if now.seconds() < window_seconds {
+        return Err(insufficient());
+    }
+    let start = now.minus_seconds(window_seconds);
+    let acc = ACCUMULATORS
+        .may_load(deps.storage, &asset.symbol)?
+        .ok_or_else(insufficient)?;
+
+    let (_, start_point) = PRICE_HISTORY
+        .prefix(&asset.symbol)
+        .range(deps.storage, None, None, Order::Descending)
+        .find(|item| item.as_ref().map_or(true, |(_, point)| point.time <= start))
+        .transpose()?
+        .ok_or_else(insufficient)?;
+    let start_acc = Accumulator {
+        cumulative: start_point.cumulative,
+        price: start_point.price,
+        time: start_point.time,
+    };
+
+    let sum = cumulative_at(&acc, now) - cumulative_at(&start_acc, start);
+    let twap = Decimal::try_from(sum / Decimal256::from_ratio(window_seconds, 1u64))
+        .map_err(|e| StdError::generic_err(e.to_string()))?;
+    Ok(to_json_binary(&TwapResponse {
+        symbol: asset.symbol,
+        twap,
+        window_seconds,
+        end_time: now,
+    })?)
+}
+
+fn query_round(deps: Deps<TorramQuery>, round_id: Option<u64>) -> Result<Binary, ContractError> {
+    let round_id = match round_id {
+        Some(round_id) => round_id,
+        None => CURRENT_ROUND.load(deps.storage)?,
+    };
+    let round = ROUNDS.may_load(deps.storage, round_id)?.unwrap_or(Round {
+        round_id,
+        submissions: vec![],
+        finalized_height: None,
+        prices: vec![],
+    });
+    Ok(to_json_binary(&round)?)
+}
+
+fn query_feeders(deps: Deps<TorramQuery>) -> StdResult<Binary> {
+    let feeders = FEEDERS
+        .range(deps.storage, None, None, Order::Ascending)
+        .map(|item| {
+            let (address, stats) = item?;
+            let pubkey = FEEDER_KEYS
+                .may_load(deps.storage, &address)?
+                .map(|key| key.pubkey);
+            Ok(FeederResponse {
+                address,
+                stats,
+                pubkey,
+            })
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+    to_json_binary(&FeedersResponse { feeders })
+}
+
+fn query_subscriptions(
+    deps: Deps<TorramQuery>,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<Binary> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after
+        .map(|addr| deps.api.addr_validate(&addr))
+        .transpose()?;
+    let subscriptions = SUBSCRIPTIONS
+        .range(
+            deps.storage,
+            start.as_ref().map(Bound::exclusive),
+            None,
+            Order::Ascending,
+        )
+        .take(limit)
+        .map(|item| {
+            item.map(|(subscriber, subscription)| SubscriptionResponse {
+                subscriber,
+                subscription,
+            })
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+    to_json_binary(&SubscriptionsResponse { subscriptions })
+}
+
+fn query_pending_prices(deps: Deps<TorramQuery>) -> StdResult<Binary> {
+    let prices = PENDING_PRICES
+        .range(deps.storage, None, None, Order::Ascending)
+        .map(|item| item.map(|(_, pending)| pending))
+        .collect::<StdResult<Vec<_>>>()?;
+    to_json_binary(&PendingPricesResponse { prices })
+}
+
+fn query_roles(deps: Deps<TorramQuery>) -> StdResult<Binary> {
+    let pausers = PAUSERS
+        .keys(deps.storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    to_json_binary(&RolesResponse {
+        admin: ADMIN.load(deps.storage)?,
+        pending_admin: PENDING_ADMIN.may_load(deps.storage)?,
+        pausers,
+        paused: PAUSED.load(deps.storage)?,
+    })
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn query(deps: Deps<TorramQuery>, env: Env, msg: QueryMsg) -> Result<Binary, ContractError> {
     match msg {
-        QueryMsg::GetPrices {} => to_json_binary(&PRICES.load(deps.storage)?),
-        QueryMsg::FetchFromOracle {} => query_oracle_prices(deps),
+        QueryMsg::GetPrices {} => Ok(query_prices(deps)?),
+        QueryMsg::GetPrice { symbol } => query_price(deps, symbol),
+        QueryMsg::GetPriceChecked { symbol } => Ok(to_json_binary(&load_checked_price(
+            deps.storage,
+            &env,
+            &symbol,
+        )?)?),
+        QueryMsg::GetPricesFor { symbols } => query_prices_for(deps, env, symbols),
+        QueryMsg::GetCrossRate { base, quote } => query_cross_rate(deps, env, base, quote),
+        QueryMsg::GetAsset { symbol } => Ok(to_json_binary(&load_asset(deps.storage, &symbol)?)?),
+        QueryMsg::ListAssets { start_after, limit } => query_assets(deps, start_after, limit),
+        QueryMsg::GetConfig {} => Ok(to_json_binary(&CONFIG.load(deps.storage)?)?),
+        QueryMsg::GetPriceAt { symbol, height } => query_price_at(deps, symbol, height),
+        QueryMsg::GetPriceHistory {
+            symbol,
+            start,
+            end,
+            limit,
+        } => query_price_history(deps, symbol, start, end, limit),
+        QueryMsg::GetTwap {
+            symbol,
+            window_seconds,
+        } => query_twap(deps, env, symbol, window_seconds),
+        QueryMsg::GetRound { round_id } => query_round(deps, round_id),
+        QueryMsg::ListFeeders {} => Ok(query_feeders(deps)?),
+        QueryMsg::GetRoles {} => Ok(query_roles(deps)?),
+        QueryMsg::GetBasketPrice {} => {
+            let basket = CONFIG.load(deps.storage)?.basket;
+            Ok(to_json_binary(&basket_price(deps.storage, &basket)?)?)
+        }
+        QueryMsg::ListPendingPrices {} => Ok(query_pending_prices(deps)?),
+        QueryMsg::GetSubscription { subscriber } => {
+            let subscriber = deps.api.addr_validate(&subscriber)?;
+            let subscription = SUBSCRIPTIONS
+                .may_load(deps.storage, &subscriber)?
+                .ok_or(ContractError::NotSubscribed {})?;
+            Ok(to_json_binary(&subscription)?)
+        }
+        QueryMsg::ListSubscriptions { start_after, limit } => {
+            Ok(query_subscriptions(deps, start_after, limit)?)
+        }
+        QueryMsg::FetchFromOracle {} => Ok(to_json_binary(
+            &fetch_oracle_prices(deps.storage, deps.api, &deps.querier)?.strict()?,
+        )?),
+    }
+}
+
+/// Handles failed subscriber callbacks: the failure is counted and reported,
+/// and the price update that sent the callback still succeeds.
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn reply(deps: DepsMut<TorramQuery>, _env: Env, msg: Reply) -> Result<Response, ContractError> {
+    if msg.id != SUBSCRIBER_REPLY_ID {
+        return Err(ContractError::UnknownReplyId { id: msg.id });
+    }
+    let SubMsgResult::Err(error) = msg.result else {
+        return Ok(Response::new());
+    };
+    let subscriber: Addr = from_json(&msg.payload)?;
+    let mut subscription = SUBSCRIPTIONS.load(deps.storage, &subscriber)?;
+    subscription.failures += 1;
+    SUBSCRIPTIONS.save(deps.storage, &subscriber, &subscription)?;
+    Ok(Response::new().add_event(
+        Event::new("subscriber_failed")
+            .add_attribute("subscriber", subscriber)
+            .add_attribute("error", error),
+    ))
+}
+
+/// Upgrades the contract state to this version.
+///
+/// Deployments without cw2 metadata predate the asset registry and have their
+/// `LEGACY_PRICES` converted; otherwise the stored contract must be this one at
+/// the same or an older version.
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn migrate(
+    deps: DepsMut<TorramQuery>,
+    env: Env,
+    _msg: MigrateMsg,
+) -> Result<Response, ContractError> {
+    let from = match cw2::CONTRACT.may_load(deps.storage)? {
+        None => {
+            migrate_legacy(deps.storage, &env)?;
+            "legacy".to_string()
+        }
+        Some(stored) => {
+            if stored.contract != CONTRACT_NAME {
+                return Err(ContractError::WrongContract {
+                    expected: CONTRACT_NAME.to_string(),
+                    found: stored.contract,
+                });
+            }
+            if parse_version(&stored.version)? > parse_version(CONTRACT_VERSION)? {
+                return Err(ContractError::CannotDowngrade {
+                    from: stored.version,
+                    to: CONTRACT_VERSION.to_string(),
+                });
+            }
+            stored.version
+        }
+    };
+    cw2::set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+    Ok(Response::new()
+        .add_attribute("action", "migrate")
+        .add_attribute("from_version", from)
+        .add_attribute("to_version", CONTRACT_VERSION))
+}
+
+/// `major.minor.patch`, ignoring any pre-release or build suffix.
+fn parse_version(version: &str) -> Result<(u64, u64, u64), ContractError> {
+    let core = version.split(['-', '+']).next().unwrap_or_default();
+    let mut parts = core.split('.').map(|part| part.parse::<u64>());
+    match (parts.next(), parts.next(), parts.next(), parts.next()) {
+        (Some(Ok(major)), Some(Ok(minor)), Some(Ok(patch)), None) => Ok((major, minor, patch)),
+        _ => Err(ContractError::InvalidVersion {
+            version: version.to_string(),
+        }),
+    }
+}
+
+/// Moves the five hardcoded legacy prices into the asset registry and sets up
+/// the state later releases added, with the admin as sole feeder and pauser.
+fn migrate_legacy(storage: &mut dyn Storage, env: &Env) -> Result<(), ContractError> {
+    let legacy = LEGACY_PRICES.load(storage)?;
+    let admin = ADMIN.load(storage)?;
+    FEEDERS.save(storage, &admin, &FeederStats::default())?;
+    PAUSERS.save(storage, &admin, &Empty {})?;
+    PAUSED.save(storage, &false)?;
+    CURRENT_ROUND.save(storage, &1)?;
+    CONFIG.save(
+        storage,
+        &Config {
+            min_reporters: 1,
+            keepers: vec![],
+            history_retention: DEFAULT_HISTORY_RETENTION,
+            max_age: vec![],
+            quorum: 1,
+            basket: default_basket(),
+            depeg_threshold: None,
+        },
+    )?;
+
+    let assets = [
+        ("BTC", 8, legacy.btc),
+        ("ETH", 18, legacy.eth),
+        ("USDC", 6, legacy.usdc),
+        ("USDT", 6, legacy.usdt),
+        ("DAI", 18, legacy.dai),
+    ];
+    let mut prices = vec![];
+    for (symbol, decimals, price) in assets {
+        register_asset(
+            storage,
+            AssetInfo {
+                symbol: symbol.to_string(),
+                class: AssetClass::Crypto,
+                decimals,
+                enabled: true,
+                bounds: PriceBounds::default(),
+            },
+        )?;
+        // legacy instances start at zero until the admin's first update
+        if !price.is_zero() {
+            prices.push(AssetPrice {
+                symbol: symbol.to_string(),
+                price,
+            });
+        }
+    }
+    store_prices(storage, env, &prices)?;
+    LEGACY_PRICES.remove(storage);
+    Ok(())
+}
+
+#[cfg(test)]
+mod integration_tests;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cosmwasm_std::testing::{
+        message_info, mock_env, MockApi, MockQuerier, MockStorage, MOCK_CONTRACT_ADDR,
+    };
+    use cosmwasm_std::{ContractResult, OwnedDeps, QueryRequest, SystemResult};
+    use std::marker::PhantomData;
+
+    type TorramDeps = OwnedDeps<MockStorage, MockApi, MockQuerier<TorramQuery>, TorramQuery>;
+
+    fn mock_deps(contacts: Vec<Contact>) -> TorramDeps {
+        let querier = MockQuerier::<TorramQuery>::new(&[(MOCK_CONTRACT_ADDR, &[])])
+            .with_custom_handler(move |request| {
+                assert_eq!(*request, TssConsensusQuery::GetAllContacts {}.into());
+                let response = ContactsResponse {
+                    contacts: contacts.clone(),
+                };
+                SystemResult::Ok(ContractResult::Ok(to_json_binary(&response).unwrap()))
+            });
+        OwnedDeps {
+            storage: MockStorage::default(),
+            api: MockApi::default(),
+            querier,
+            custom_query_type: PhantomData,
+        }
+    }
+
+    fn asset(symbol: &str, class: AssetClass) -> AssetInfo {
+        AssetInfo {
+            symbol: symbol.to_string(),
+            class,
+            decimals: 8,
+            enabled: true,
+            bounds: PriceBounds::default(),
+        }
+    }
+
+    fn price(symbol: &str, value: &str) -> AssetPrice {
+        AssetPrice {
+            symbol: symbol.to_string(),
+            price: value.parse().unwrap(),
+        }
+    }
+
+    fn setup(contacts: Vec<Contact>) -> (TorramDeps, Addr) {
+        let mut deps = mock_deps(contacts);
+        let admin = deps.api.addr_make("admin");
+        let msg = InstantiateMsg {
+            admin: admin.to_string(),
+            assets: vec![
+                asset("BTC", AssetClass::Crypto),
+                asset("usdc", AssetClass::Crypto),
+                asset("SPX", AssetClass::Rwa),
+            ],
+            min_reporters: None,
+            history_retention: Some(3),
+            max_age: vec![MaxAge {
+                class: AssetClass::Crypto,
+                seconds: 60,
+            }],
+            feeders: None,
+            quorum: None,
+            pausers: None,
+            basket: None,
+            depeg_threshold: None,
+        };
+        let info = message_info(&admin, &[]);
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+        (deps, admin)
+    }
+
+    fn update(
+        deps: &mut TorramDeps,
+        sender: &Addr,
+        prices: Vec<AssetPrice>,
+    ) -> Result<Response, ContractError> {
+        update_at(deps, mock_env(), sender, prices)
+    }
+
+    fn update_at(
+        deps: &mut TorramDeps,
+        env: Env,
+        sender: &Addr,
+        prices: Vec<AssetPrice>,
+    ) -> Result<Response, ContractError> {
+        let msg = ExecuteMsg::UpdatePrices { prices };
+        execute(deps.as_mut(), env, message_info(sender, &[]), msg)
+    }
+
+    /// `mock_env()` advanced by `blocks` blocks of five seconds each.
+    fn env_after(blocks: u64) -> Env {
+        let mut env = mock_env();
+        env.block.height += blocks;
+        env.block.time = env.block.time.plus_seconds(blocks * 5);
+        env
+    }
+
+    #[test]
+    fn instantiate_registers_assets_with_zero_prices() {
+        let (deps, _) = setup(vec![]);
+
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::GetPrices {}).unwrap();
+        let data: PriceData = from_json(res).unwrap();
+        assert_eq!(
+            data.prices,
+            vec![price("BTC", "0"), price("SPX", "0"), price("USDC", "0")]
+        );
+
+        let res = query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::GetAsset {
+                symbol: "spx".to_string(),
+            },
+        )
+        .unwrap();
+        let info: AssetInfo = from_json(res).unwrap();
+        assert_eq!(info, asset("SPX", AssetClass::Rwa));
+    }
+
+    #[test]
+    fn update_prices_requires_feeder_and_registered_assets() {
+        let (mut deps, admin) = setup(vec![]);
+        let stranger = deps.api.addr_make("stranger");
+
+        let err = update(&mut deps, &stranger, vec![price("BTC", "1")]).unwrap_err();
+        assert!(matches!(err, ContractError::NotFeeder {}));
+
+        let err = update(&mut deps, &admin, vec![price("ETH", "1")]).unwrap_err();
+        assert!(matches!(err, ContractError::UnknownAsset { symbol } if symbol == "ETH"));
+
+        let err = update(
+            &mut deps,
+            &admin,
+            vec![price("BTC", "1"), price("btc", "2")],
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::DuplicatePrice { .. }));
+
+        let res = update(
+            &mut deps,
+            &admin,
+            vec![price("BTC", "109290.5"), price("USDC", "0.9998")],
+        )
+        .unwrap();
+        let event = &res.events[0];
+        assert_eq!(event.ty, "oracle_prices");
+        assert_eq!(attr(event, "version"), PRICES_EVENT_VERSION);
+        assert_eq!(attr(event, "feeder"), admin.as_str());
+        assert_eq!(attr(event, "btc"), "109290.5");
+        assert_eq!(attr(event, "usdc"), "0.9998");
+        assert!(event.attributes.iter().all(|a| a.key != "btc_previous"));
+
+        let res = query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::GetPrice {
+                symbol: "btc".to_string(),
+            },
+        )
+        .unwrap();
+        let stored: PriceResponse = from_json(res).unwrap();
+        assert_eq!(stored.symbol, "BTC");
+        assert_eq!(stored.price, "109290.5".parse::<Decimal>().unwrap());
+        assert_eq!(stored.last_updated_height, mock_env().block.height);
+
+        let res = update(&mut deps, &admin, vec![price("BTC", "98361.45")]).unwrap();
+        let event = &res.events[0];
+        assert_eq!(attr(event, "btc_previous"), "109290.5");
+        assert_eq!(attr(event, "btc_change_pct"), "-10");
+        assert_eq!(attr(event, "average"), "0.9998");
+
+        // a micro-priced asset jumping to a few units still updates
+        update(
+            &mut deps,
+            &admin,
+            vec![price("SPX", "0.000000000000000001")],
+        )
+        .unwrap();
+        let res = update(&mut deps, &admin, vec![price("SPX", "5")]).unwrap();
+        assert_eq!(attr(&res.events[0], "spx_change_pct"), "overflow");
+        assert_eq!(
+            PRICES.load(&deps.storage, "SPX").unwrap().price,
+            Decimal::percent(500)
+        );
+    }
+
+    #[test]
+    fn disabled_assets_are_skipped() {
+        let (mut deps, admin) = setup(vec![]);
+        let msg = ExecuteMsg::UpdateAsset {
+            symbol: "SPX".to_string(),
+            class: None,
+            decimals: None,
+            enabled: Some(false),
+            bounds: None,
+        };
+        execute(deps.as_mut(), mock_env(), message_info(&admin, &[]), msg).unwrap();
+
+        let err = update(&mut deps, &admin, vec![price("SPX", "5000")]).unwrap_err();
+        assert!(matches!(err, ContractError::AssetDisabled { .. }));
+
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::GetPrices {}).unwrap();
+        let data: PriceData = from_json(res).unwrap();
+        assert_eq!(data.prices.len(), 2);
+    }
+
+    fn attr<'a>(event: &'a Event, key: &str) -> &'a str {
+        let attribute = event.attributes.iter().find(|a| a.key == key);
+        &attribute.unwrap().value
+    }
+
+    fn contact(address: &str, prices: &str) -> Contact {
+        Contact {
+            address: address.to_string(),
+            contact: prices.to_string(),
+        }
+    }
+
+    #[test]
+    fn fetch_from_oracle_reads_every_enabled_asset() {
+        let contacts = vec![contact(
+            "torram1validator",
+            "BTC:109290.5,ETH:2627.48,USDC:0.9999,SPX:5431.2",
+        )];
+        let (deps, _) = setup(contacts);
+
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::FetchFromOracle {}).unwrap();
+        let data: OraclePrices = from_json(res).unwrap();
+        assert_eq!(
+            data.prices,
+            vec![
+                price("BTC", "109290.5"),
+                price("SPX", "5431.2"),
+                price("USDC", "0.9999")
+            ]
+        );
+        assert_eq!(data.reporters["torram1validator"], data.prices);
+    }
+
+    #[test]
+    fn fetch_from_oracle_takes_median_across_reporters() {
+        let contacts = vec![
+            contact("torram1a", "BTC:100,USDC:1.0,SPX:10"),
+            contact("torram1b", "BTC:10929,USDC:0.99,SPX:12"),
+            contact("torram1c", " \"BTC\": 102, usdc:'1.01', SPX : 11 "),
+            contact("torram1d", "BTC:104,USDC:oops"),
+            contact("torram1e", "BTC:103,SPX:11,garbage"),
+            contact("torram1f", "BTC:1,BTC:1000"),
+        ];
+        let (deps, _) = setup(contacts);
+
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::FetchFromOracle {}).unwrap();
+        let data: OraclePrices = from_json(res).unwrap();
+        assert_eq!(
+            data.prices,
+            vec![price("BTC", "102"), price("SPX", "11"), price("USDC", "1")]
+        );
+        // malformed contacts are dropped as a whole
+        assert_eq!(data.reporters.len(), 3);
+        assert!(!data.reporters.contains_key("torram1d"));
+    }
+
+    #[test]
+    fn fetch_from_oracle_enforces_min_reporters() {
+        let contacts = vec![
+            contact("torram1a", "BTC:100,USDC:1.0,SPX:10"),
+            contact("torram1b", "BTC:102,USDC:1.0"),
+        ];
+        let (mut deps, admin) = setup(contacts);
+        let msg = ExecuteMsg::UpdateConfig(ConfigUpdate {
+            min_reporters: Some(2),
+            ..ConfigUpdate::default()
+        });
+        execute(deps.as_mut(), mock_env(), message_info(&admin, &[]), msg).unwrap();
+
+        let err = query(deps.as_ref(), mock_env(), QueryMsg::FetchFromOracle {}).unwrap_err();
+        assert!(matches!(
+            err,
+            ContractError::InsufficientReporters { symbol, got: 1, need: 2 } if symbol == "SPX"
+        ));
+    }
+
+    #[test]
+    fn sync_from_oracle_stores_median_prices() {
+        let contacts = vec![
+            contact("torram1a", "BTC:100,USDC:1.0,SPX:10"),
+            contact("torram1b", "BTC:102,USDC:0.98,SPX:12"),
+        ];
+        let (mut deps, admin) = setup(contacts);
+        let keeper = deps.api.addr_make("keeper");
+
+        // permissionless while no keepers are configured
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            message_info(&keeper, &[]),
+            ExecuteMsg::SyncFromOracle {},
+        )
+        .unwrap();
+        assert_eq!(res.events[0].ty, "oracle_prices");
+        assert_eq!(attr(&res.events[0], "feeder"), keeper.as_str());
+        assert_eq!(attr(&res.events[0], "usdc"), "0.99");
+
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::GetPrices {}).unwrap();
+        let data: PriceData = from_json(res).unwrap();
+        assert_eq!(
+            data.prices,
+            vec![
+                price("BTC", "101"),
+                price("SPX", "11"),
+                price("USDC", "0.99")
+            ]
+        );
+
+        let msg = ExecuteMsg::UpdateConfig(ConfigUpdate {
+            keepers: Some(vec![keeper.to_string()]),
+            ..ConfigUpdate::default()
+        });
+        execute(deps.as_mut(), mock_env(), message_info(&admin, &[]), msg).unwrap();
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            message_info(&admin, &[]),
+            ExecuteMsg::SyncFromOracle {},
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::NotKeeper {}));
+    }
+
+    #[test]
+    fn sync_from_oracle_skips_under_quoted_assets() {
+        let contacts = vec![
+            contact("torram1a", "BTC:100,USDC:1.0"),
+            contact("torram1b", "BTC:102,USDC:0.98"),
+        ];
+        let (mut deps, _) = setup(contacts);
+        let keeper = deps.api.addr_make("keeper");
+
+        // SPX is registered but no feeder quotes it yet
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            message_info(&keeper, &[]),
+            ExecuteMsg::SyncFromOracle {},
+        )
+        .unwrap();
+        let skipped = res.attributes.iter().find(|a| a.key == "skipped").unwrap();
+        assert_eq!(skipped.value, "SPX");
+        let btc: PriceResponse = from_json(
+            query(
+                deps.as_ref(),
+                mock_env(),
+                QueryMsg::GetPrice {
+                    symbol: "BTC".to_string(),
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(btc.price, Decimal::percent(10100));
+
+        // the query stays strict
+        let err = query(deps.as_ref(), mock_env(), QueryMsg::FetchFromOracle {}).unwrap_err();
+        assert!(matches!(
+            err,
+            ContractError::InsufficientReporters { symbol, got: 0, need: 1 } if symbol == "SPX"
+        ));
+    }
+
+    #[test]
+    fn price_history_is_bounded_and_queryable_by_height() {
+        let (mut deps, admin) = setup(vec![]);
+        let base = mock_env().block.height;
+        for (blocks, value) in [(0, "100"), (10, "110"), (20, "120"), (30, "130")] {
+            update_at(
+                &mut deps,
+                env_after(blocks),
+                &admin,
+                vec![price("BTC", value)],
+            )
+            .unwrap();
+        }
+
+        let at = |deps: &TorramDeps, height: u64| {
+            query(
+                deps.as_ref(),
+                mock_env(),
+                QueryMsg::GetPriceAt {
+                    symbol: "BTC".to_string(),
+                    height,
+                },
+            )
+        };
+        let point: PricePoint = from_json(at(&deps, base + 25).unwrap()).unwrap();
+        assert_eq!(point.height, base + 20);
+        assert_eq!(point.price, "120".parse::<Decimal>().unwrap());
+        assert_eq!(point.time, env_after(20).block.time);
+
+        // retention is 3, so the first point has been pruned
+        let err = at(&deps, base + 5).unwrap_err();
+        assert!(matches!(err, ContractError::NoPriceAt { .. }));
+
+        let res = query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::GetPriceHistory {
+                symbol: "btc".to_string(),
+                start: Some(base + 15),
+                end: None,
+                limit: Some(1),
+            },
+        )
+        .unwrap();
+        let history: PriceHistoryResponse = from_json(res).unwrap();
+        assert_eq!(history.symbol, "BTC");
+        assert_eq!(history.points.len(), 1);
+        assert_eq!(history.points[0].height, base + 20);
+    }
+
+    #[test]
+    fn twap_weights_prices_by_time_in_effect() {
+        let (mut deps, admin) = setup(vec![]);
+        // 100 for 50s, then 200 for 100s, then 400 for 50s
+        update_at(&mut deps, env_after(0), &admin, vec![price("BTC", "100")]).unwrap();
+        update_at(&mut deps, env_after(10), &admin, vec![price("BTC", "200")]).unwrap();
+        update_at(&mut deps, env_after(30), &admin, vec![price("BTC", "400")]).unwrap();
+
+        let twap = |window_seconds: u64| {
+            query(
+                deps.as_ref(),
+                env_after(40),
+                QueryMsg::GetTwap {
+                    symbol: "BTC".to_string(),
+                    window_seconds,
+                },
+            )
+        };
+        let res: TwapResponse = from_json(twap(200).unwrap()).unwrap();
+        assert_eq!(res.twap, "225".parse::<Decimal>().unwrap());
+        assert_eq!(res.end_time, env_after(40).block.time);
+
+        // window starting halfway through the 200 period
+        let res: TwapResponse = from_json(twap(100).unwrap()).unwrap();
+        assert_eq!(res.twap, "300".parse::<Decimal>().unwrap());
+
+        let err = twap(201).unwrap_err();
+        assert!(matches!(err, ContractError::InsufficientHistory { .. }));
+        let err = twap(0).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidTwapWindow {}));
+    }
+
+    #[test]
+    fn checked_price_rejects_placeholders_and_stale_prices() {
+        let (mut deps, admin) = setup(vec![]);
+        let checked = |deps: &TorramDeps, env: Env, symbol: &str| {
+            query(
+                deps.as_ref(),
+                env,
+                QueryMsg::GetPriceChecked {
+                    symbol: symbol.to_string(),
+                },
+            )
+        };
+
+        let err = checked(&deps, env_after(0), "BTC").unwrap_err();
+        assert!(matches!(err, ContractError::PriceNotSet { .. }));
+
+        update_at(
+            &mut deps,
+            env_after(0),
+            &admin,
+            vec![price("BTC", "100"), price("SPX", "5000")],
+        )
+        .unwrap();
+        let res: PriceResponse = from_json(checked(&deps, env_after(12), "BTC").unwrap()).unwrap();
+        assert_eq!(res.last_updated_time, env_after(0).block.time);
+
+        // crypto max age is 60s
+        let err = checked(&deps, env_after(13), "BTC").unwrap_err();
+        assert!(matches!(
+            err,
+            ContractError::StalePrice {
+                age_seconds: 65,
+                max_age: 60,
+                ..
+            }
+        ));
+        // no limit configured for RWAs
+        checked(&deps, env_after(1000), "SPX").unwrap();
+    }
+
+    #[test]
+    fn rounds_finalize_at_quorum_with_median() {
+        let (mut deps, admin) = setup(vec![]);
+        let feeders: Vec<Addr> = ["alice", "bob", "carol"]
+            .iter()
+            .map(|name| deps.api.addr_make(name))
+            .collect();
+        let msg = ExecuteMsg::UpdateFeeders {
+            add: feeders.iter().map(|f| f.to_string()).collect(),
+            remove: vec![admin.to_string()],
+        };
+        execute(deps.as_mut(), mock_env(), message_info(&admin, &[]), msg).unwrap();
+        let msg = ExecuteMsg::UpdateConfig(ConfigUpdate {
+            quorum: Some(4),
+            ..ConfigUpdate::default()
+        });
+        let err = execute(deps.as_mut(), mock_env(), message_info(&admin, &[]), msg).unwrap_err();
+        assert!(matches!(
+            err,
+            ContractError::InvalidQuorum {
+                quorum: 4,
+                feeders: 3
+            }
+        ));
+        let msg = ExecuteMsg::UpdateConfig(ConfigUpdate {
+            quorum: Some(2),
+            ..ConfigUpdate::default()
+        });
+        execute(deps.as_mut(), mock_env(), message_info(&admin, &[]), msg).unwrap();
+
+        let submit = |deps: &mut TorramDeps, feeder: &Addr, round_id: u64, prices| {
+            let msg = ExecuteMsg::SubmitPrices { round_id, prices };
+            execute(deps.as_mut(), mock_env(), message_info(feeder, &[]), msg)
+        };
+
+        let err = update(&mut deps, &admin, vec![price("BTC", "1")]).unwrap_err();
+        assert!(matches!(err, ContractError::NotFeeder {}));
+        let err = submit(&mut deps, &feeders[0], 2, vec![price("BTC", "100")]).unwrap_err();
+        assert!(matches!(
+            err,
+            ContractError::WrongRound {
+                expected: 1,
+                got: 2
+            }
+        ));
+
+        let res = submit(
+            &mut deps,
+            &feeders[0],
+            1,
+            vec![price("BTC", "100"), price("SPX", "5000")],
+        )
+        .unwrap();
+        assert!(res.events.is_empty());
+        let err = submit(&mut deps, &feeders[0], 1, vec![price("BTC", "100")]).unwrap_err();
+        assert!(matches!(
+            err,
+            ContractError::AlreadySubmitted { round_id: 1 }
+        ));
+
+        // SPX only has one quote, so only BTC is finalized
+        let res = submit(&mut deps, &feeders[1], 1, vec![price("BTC", "110")]).unwrap();
+        assert_eq!(res.events[0].ty, "oracle_prices");
+
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::GetPrices {}).unwrap();
+        let data: PriceData = from_json(res).unwrap();
+        assert_eq!(
+            data.prices,
+            vec![price("BTC", "105"), price("SPX", "0"), price("USDC", "0")]
+        );
+
+        let res = query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::GetRound { round_id: Some(1) },
+        )
+        .unwrap();
+        let round: Round = from_json(res).unwrap();
+        assert_eq!(round.finalized_height, Some(mock_env().block.height));
+        assert_eq!(round.prices, vec![price("BTC", "105")]);
+        let res = query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::GetRound { round_id: None },
+        )
+        .unwrap();
+        let round: Round = from_json(res).unwrap();
+        assert_eq!(round.round_id, 2);
+
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::ListFeeders {}).unwrap();
+        let list: FeedersResponse = from_json(res).unwrap();
+        let alice = list
+            .feeders
+            .iter()
+            .find(|f| f.address == feeders[0])
+            .unwrap();
+        assert_eq!(alice.stats.submissions, 1);
+        assert_eq!(alice.stats.finalized_rounds, 1);
+        let carol = list
+            .feeders
+            .iter()
+            .find(|f| f.address == feeders[2])
+            .unwrap();
+        assert_eq!(carol.stats, FeederStats::default());
+    }
+
+    #[test]
+    fn admin_transfer_and_pause() {
+        let (mut deps, admin) = setup(vec![]);
+        let new_admin = deps.api.addr_make("new_admin");
+        let pauser = deps.api.addr_make("pauser");
+        let run = |deps: &mut TorramDeps, sender: &Addr, msg: ExecuteMsg| {
+            execute(deps.as_mut(), mock_env(), message_info(sender, &[]), msg)
+        };
+
+        let err = run(&mut deps, &new_admin, ExecuteMsg::AcceptAdmin {}).unwrap_err();
+        assert!(matches!(err, ContractError::NoPendingAdmin {}));
+        let propose = ExecuteMsg::ProposeAdmin {
+            new_admin: new_admin.to_string(),
+        };
+        let err = run(&mut deps, &new_admin, propose.clone()).unwrap_err();
+        assert!(matches!(err, ContractError::NotAdmin {}));
+        run(&mut deps, &admin, propose).unwrap();
+        let err = run(&mut deps, &pauser, ExecuteMsg::AcceptAdmin {}).unwrap_err();
+        assert!(matches!(err, ContractError::NotPendingAdmin {}));
+        run(&mut deps, &new_admin, ExecuteMsg::AcceptAdmin {}).unwrap();
+
+        let pausers = ExecuteMsg::UpdatePausers {
+            add: vec![pauser.to_string()],
+            remove: vec![admin.to_string()],
+        };
+        let err = run(&mut deps, &admin, pausers.clone()).unwrap_err();
+        assert!(matches!(err, ContractError::NotAdmin {}));
+        run(&mut deps, &new_admin, pausers).unwrap();
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::GetRoles {}).unwrap();
+        let roles: RolesResponse = from_json(res).unwrap();
+        assert_eq!(roles.admin, new_admin);
+        assert_eq!(roles.pending_admin, None);
+        assert_eq!(roles.pausers, vec![pauser.clone()]);
+        assert!(!roles.paused);
+
+        update(&mut deps, &admin, vec![price("BTC", "100")]).unwrap();
+        let err = run(&mut deps, &new_admin, ExecuteMsg::Pause {}).unwrap_err();
+        assert!(matches!(err, ContractError::NotPauser {}));
+        run(&mut deps, &pauser, ExecuteMsg::Pause {}).unwrap();
+
+        let err = update(&mut deps, &admin, vec![price("BTC", "101")]).unwrap_err();
+        assert!(matches!(err, ContractError::Paused {}));
+        let err = run(&mut deps, &admin, ExecuteMsg::SyncFromOracle {}).unwrap_err();
+        assert!(matches!(err, ContractError::Paused {}));
+        let checked = QueryMsg::GetPriceChecked {
+            symbol: "BTC".to_string(),
+        };
+        let err = query(deps.as_ref(), mock_env(), checked.clone()).unwrap_err();
+        assert!(matches!(err, ContractError::Paused {}));
+        let plain = QueryMsg::GetPrice {
+            symbol: "BTC".to_string(),
+        };
+        query(deps.as_ref(), mock_env(), plain).unwrap();
+
+        run(&mut deps, &pauser, ExecuteMsg::Unpause {}).unwrap();
+        query(deps.as_ref(), mock_env(), checked).unwrap();
+        update(&mut deps, &admin, vec![price("BTC", "101")]).unwrap();
+    }
+
+    #[test]
+    fn price_bounds_and_force_update() {
+        let (mut deps, admin) = setup(vec![]);
+        let bob = deps.api.addr_make("bob");
+        let run = |deps: &mut TorramDeps, sender: &Addr, msg: ExecuteMsg| {
+            execute(deps.as_mut(), mock_env(), message_info(sender, &[]), msg)
+        };
+        let msg = ExecuteMsg::UpdateFeeders {
+            add: vec![bob.to_string()],
+            remove: vec![],
+        };
+        run(&mut deps, &admin, msg).unwrap();
+        let bounds = |min: &str, max: &str| PriceBounds {
+            max_deviation: Some("0.5".parse().unwrap()),
+            min_price: Some(min.parse().unwrap()),
+            max_price: Some(max.parse().unwrap()),
+        };
+        let set_bounds = |bounds| ExecuteMsg::UpdateAsset {
+            symbol: "BTC".to_string(),
+            class: None,
+            decimals: None,
+            enabled: None,
+            bounds: Some(bounds),
+        };
+        let err = run(&mut deps, &admin, set_bounds(bounds("10", "1"))).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidBounds { .. }));
+        run(&mut deps, &admin, set_bounds(bounds("1000", "1000000"))).unwrap();
+
+        let err = update(&mut deps, &admin, vec![price("BTC", "0")]).unwrap_err();
+        assert!(matches!(err, ContractError::ZeroPrice { .. }));
+
+        // Out of bounds: dropped with an event, nothing stored.
+        let res = update(&mut deps, &admin, vec![price("BTC", "10")]).unwrap();
+        let rejected = res
+            .events
+            .iter()
+            .find(|e| e.ty == "price_rejected")
+            .unwrap();
+        assert!(rejected
+            .attributes
+            .iter()
+            .any(|a| a.key == "reason" && a.value == "out_of_bounds"));
+        let stored = PRICES.load(&deps.storage, "BTC").unwrap();
+        assert!(stored.is_placeholder());
+
+        update(&mut deps, &admin, vec![price("BTC", "100000")]).unwrap();
+        // A 10x move is held back until confirmed by someone else.
+        let res = update(&mut deps, &bob, vec![price("BTC", "1000000")]).unwrap();
+        assert!(res.events.iter().any(|e| e.ty == "price_rejected"));
+        assert_eq!(
+            PRICES.load(&deps.storage, "BTC").unwrap().price,
+            "100000".parse::<Decimal>().unwrap()
+        );
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::ListPendingPrices {}).unwrap();
+        let pending: PendingPricesResponse = from_json(res).unwrap();
+        assert_eq!(pending.prices.len(), 1);
+        assert_eq!(pending.prices[0].proposed_by, vec![bob.clone()]);
+
+        let force = ExecuteMsg::ForceUpdate {
+            symbol: "btc".to_string(),
+        };
+        let err = run(&mut deps, &bob, force.clone()).unwrap_err();
+        assert!(matches!(err, ContractError::CannotConfirmOwnPrice {}));
+        let stranger = deps.api.addr_make("stranger");
+        let err = run(&mut deps, &stranger, force.clone()).unwrap_err();
+        assert!(matches!(err, ContractError::NotFeeder {}));
+        run(&mut deps, &admin, force.clone()).unwrap();
+        assert_eq!(
+            PRICES.load(&deps.storage, "BTC").unwrap().price,
+            "1000000".parse::<Decimal>().unwrap()
+        );
+        let err = run(&mut deps, &admin, force).unwrap_err();
+        assert!(matches!(err, ContractError::NoPendingPrice { .. }));
+    }
+
+    #[test]
+    fn deviation_overflow_holds_the_price() {
+        let (mut deps, admin) = setup(vec![]);
+        let msg = ExecuteMsg::UpdateAsset {
+            symbol: "BTC".to_string(),
+            class: None,
+            decimals: None,
+            enabled: None,
+            bounds: Some(PriceBounds {
+                max_deviation: Some(Decimal::percent(50)),
+                min_price: None,
+                max_price: None,
+            }),
+        };
+        execute(deps.as_mut(), mock_env(), message_info(&admin, &[]), msg).unwrap();
+        update(
+            &mut deps,
+            &admin,
+            vec![price("BTC", "0.000000000000000001")],
+        )
+        .unwrap();
+
+        // (1000 - 1e-18) / 1e-18 does not fit in a Decimal
+        let res = update(&mut deps, &admin, vec![price("BTC", "1000")]).unwrap();
+        let rejected = res
+            .events
+            .iter()
+            .find(|e| e.ty == "price_rejected")
+            .unwrap();
+        assert_eq!(attr(rejected, "reason"), "deviation");
+        assert_eq!(attr(rejected, "deviation"), "overflow");
+        assert!(PENDING_PRICES.has(&deps.storage, "BTC"));
+        assert_eq!(
+            PRICES.load(&deps.storage, "BTC").unwrap().price,
+            Decimal::raw(1)
+        );
+    }
+
+    #[test]
+    fn cross_rate_divides_checked_prices() {
+        let (mut deps, admin) = setup(vec![]);
+        let cross = |deps: &TorramDeps, env: Env, base: &str, quote: &str| {
+            let msg = QueryMsg::GetCrossRate {
+                base: base.to_string(),
+                quote: quote.to_string(),
+            };
+            query(deps.as_ref(), env, msg)
+        };
+
+        update_at(&mut deps, env_after(0), &admin, vec![price("BTC", "30000")]).unwrap();
+        let err = cross(&deps, env_after(0), "SPX", "BTC").unwrap_err();
+        assert!(matches!(err, ContractError::PriceNotSet { symbol } if symbol == "SPX"));
+
+        update_at(
+            &mut deps,
+            env_after(1),
+            &admin,
+            vec![price("SPX", "5000"), price("USDC", "0.9998")],
+        )
+        .unwrap();
+        let res: CrossRateResponse =
+            from_json(cross(&deps, env_after(2), "spx", "btc").unwrap()).unwrap();
+        // 5000 / 30000 = 0.1666..., rounded down to BTC's 8 decimals
+        assert_eq!(res.rate, "0.16666666".parse::<Decimal>().unwrap());
+        assert_eq!(res.decimals, 8);
+        assert_eq!(res.last_updated_time, env_after(0).block.time);
+
+        let res: CrossRateResponse =
+            from_json(cross(&deps, env_after(2), "BTC", "USDC").unwrap()).unwrap();
+        assert_eq!(res.rate, "30006.00120024".parse::<Decimal>().unwrap());
+
+        // the BTC leg is older than the 60s crypto max age
+        let err = cross(&deps, env_after(13), "SPX", "BTC").unwrap_err();
+        assert!(matches!(err, ContractError::StalePrice { symbol, .. } if symbol == "BTC"));
+    }
+
+    #[test]
+    fn weighted_basket_and_depeg_alerts() {
+        let (mut deps, admin) = setup(vec![]);
+        let query_basket =
+            |deps: &TorramDeps| query(deps.as_ref(), mock_env(), QueryMsg::GetBasketPrice {});
+        let err = query_basket(&deps).unwrap_err();
+        assert!(matches!(err, ContractError::EmptyBasket {}));
+
+        let register = ExecuteMsg::RegisterAsset {
+            symbol: "PYUSD".to_string(),
+            class: AssetClass::Crypto,
+            decimals: 6,
+            enabled: true,
+            bounds: PriceBounds::default(),
+        };
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            message_info(&admin, &[]),
+            register,
+        )
+        .unwrap();
+        let member = |symbol: &str, weight: &str| BasketMember {
+            symbol: symbol.to_string(),
+            weight: Some(weight.parse().unwrap()),
+        };
+        let config = |basket| {
+            ExecuteMsg::UpdateConfig(ConfigUpdate {
+                basket: Some(basket),
+                depeg_threshold: Some("0.02".parse().unwrap()),
+                ..ConfigUpdate::default()
+            })
+        };
+        let msg = config(vec![member("usdc", "3"), member("USDC", "1")]);
+        let err = execute(deps.as_mut(), mock_env(), message_info(&admin, &[]), msg).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidBasketMember { .. }));
+        let msg = config(vec![member("usdc", "3"), member("pyusd", "1")]);
+        execute(deps.as_mut(), mock_env(), message_info(&admin, &[]), msg).unwrap();
+
+        let res = update(
+            &mut deps,
+            &admin,
+            vec![price("USDC", "1.0"), price("PYUSD", "0.96")],
+        )
+        .unwrap();
+        let oracle = &res.events[0];
+        assert_eq!(oracle.ty, "oracle_prices");
+        assert_eq!(attr(oracle, "average"), "0.99");
+        let depegs: Vec<_> = res
+            .events
+            .iter()
+            .filter(|e| e.ty == "stablecoin_depeg")
+            .collect();
+        assert_eq!(depegs.len(), 1);
+        assert_eq!(depegs[0].attributes[0].value, "PYUSD");
+        assert_eq!(depegs[0].attributes[2].value, "0.04");
+
+        let basket: BasketPriceResponse = from_json(query_basket(&deps).unwrap()).unwrap();
+        assert_eq!(basket.price, "0.99".parse::<Decimal>().unwrap());
+        assert_eq!(basket.members.len(), 2);
+
+        let clear = |depeg_threshold| {
+            ExecuteMsg::UpdateConfig(ConfigUpdate {
+                depeg_threshold,
+                clear_depeg_threshold: true,
+                ..ConfigUpdate::default()
+            })
+        };
+        let msg = clear(Some("0.05".parse().unwrap()));
+        let err = execute(deps.as_mut(), mock_env(), message_info(&admin, &[]), msg).unwrap_err();
+        assert!(matches!(err, ContractError::ConflictingDepegThreshold {}));
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            message_info(&admin, &[]),
+            clear(None),
+        )
+        .unwrap();
+        assert_eq!(CONFIG.load(&deps.storage).unwrap().depeg_threshold, None);
+        let res = update(&mut deps, &admin, vec![price("PYUSD", "0.9")]).unwrap();
+        assert!(!res.events.iter().any(|e| e.ty == "stablecoin_depeg"));
+    }
+
+    #[test]
+    fn extreme_basket_weights_cannot_block_updates() {
+        let (mut deps, admin) = setup(vec![]);
+        let msg = ExecuteMsg::UpdateConfig(ConfigUpdate {
+            basket: Some(vec![BasketMember {
+                symbol: "USDC".to_string(),
+                weight: Some("200000000000000000000".parse().unwrap()),
+            }]),
+            ..ConfigUpdate::default()
+        });
+        execute(deps.as_mut(), mock_env(), message_info(&admin, &[]), msg).unwrap();
+
+        // price * weight is past Decimal::MAX
+        let res = update(&mut deps, &admin, vec![price("USDC", "2")]).unwrap();
+        assert_eq!(attr(&res.events[0], "average"), "overflow");
+        assert_eq!(
+            PRICES.load(&deps.storage, "USDC").unwrap().price,
+            Decimal::percent(200)
+        );
+        let err = query(deps.as_ref(), mock_env(), QueryMsg::GetBasketPrice {}).unwrap_err();
+        assert!(matches!(err, ContractError::BasketOverflow {}));
+    }
+
+    #[test]
+    fn extreme_subscription_thresholds_cannot_block_updates() {
+        let (mut deps, admin) = setup(vec![]);
+        let consumer = deps.api.addr_make("consumer");
+        let subscribe = |deviation_bps| ExecuteMsg::Subscribe {
+            symbols: vec!["BTC".to_string()],
+            deviation_bps,
+        };
+        for deviation_bps in [0, 10_001, u32::MAX] {
+            let info = message_info(&consumer, &[]);
+            let err = execute(deps.as_mut(), mock_env(), info, subscribe(deviation_bps));
+            assert!(matches!(
+                err.unwrap_err(),
+                ContractError::InvalidDeviationBps {}
+            ));
+        }
+
+        // a subscription stored before the range check existed
+        let large = Decimal::from_ratio(10u128.pow(15), 1u128);
+        update(&mut deps, &admin, vec![price("BTC", &large.to_string())]).unwrap();
+        let subscription = Subscription {
+            symbols: vec!["BTC".to_string()],
+            deviation_bps: u32::MAX,
+            notified: vec![AssetPrice {
+                symbol: "BTC".to_string(),
+                price: large,
+            }],
+            failures: 0,
+        };
+        SUBSCRIPTIONS
+            .save(&mut deps.storage, &consumer, &subscription)
+            .unwrap();
+
+        let res = update(&mut deps, &admin, vec![price("BTC", "2000000000000000")]).unwrap();
+        assert_eq!(res.messages.len(), 1);
+        assert_eq!(
+            PRICES.load(&deps.storage, "BTC").unwrap().price,
+            "2000000000000000".parse::<Decimal>().unwrap()
+        );
+    }
+
+    #[test]
+    fn subscribers_are_called_back_past_their_threshold() {
+        let (mut deps, admin) = setup(vec![]);
+        let consumer = deps.api.addr_make("consumer");
+        let subscribe = |symbols: &[&str]| ExecuteMsg::Subscribe {
+            symbols: symbols.iter().map(|s| s.to_string()).collect(),
+            deviation_bps: 100,
+        };
+        let info = message_info(&consumer, &[]);
+        let err = execute(deps.as_mut(), mock_env(), info.clone(), subscribe(&[])).unwrap_err();
+        assert!(matches!(err, ContractError::NoSymbols {}));
+        let msg = subscribe(&["btc", "ETH"]);
+        let err = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap_err();
+        assert!(matches!(err, ContractError::UnknownAsset { .. }));
+        execute(deps.as_mut(), mock_env(), info, subscribe(&["btc", "usdc"])).unwrap();
+
+        let callback = |res: &Response| -> Vec<PriceResponse> {
+            assert_eq!(res.messages.len(), 1);
+            let sub_msg = &res.messages[0];
+            assert_eq!(sub_msg.id, SUBSCRIBER_REPLY_ID);
+            assert_eq!(sub_msg.reply_on, cosmwasm_std::ReplyOn::Error);
+            assert_eq!(sub_msg.gas_limit, Some(SUBSCRIBER_GAS_LIMIT));
+            let CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr, msg, ..
+            }) = &sub_msg.msg
+            else {
+                panic!("unexpected message {:?}", sub_msg.msg);
+            };
+            assert_eq!(contract_addr, consumer.as_str());
+            let SubscriberMsg::OraclePriceUpdate { prices } = from_json(msg).unwrap();
+            prices
+        };
+
+        // no reference price yet, so the first update is always pushed
+        let res = update(&mut deps, &admin, vec![price("BTC", "100")]).unwrap();
+        assert_eq!(callback(&res)[0].price, "100".parse::<Decimal>().unwrap());
+        let res = update(&mut deps, &admin, vec![price("BTC", "100.5")]).unwrap();
+        assert!(res.messages.is_empty());
+        let res = update(
+            &mut deps,
+            &admin,
+            vec![price("BTC", "99"), price("SPX", "5000")],
+        )
+        .unwrap();
+        let prices = callback(&res);
+        assert_eq!(prices.len(), 1);
+        assert_eq!(prices[0].symbol, "BTC");
+
+        let msg = QueryMsg::GetSubscription {
+            subscriber: consumer.to_string(),
+        };
+        let res = query(deps.as_ref(), mock_env(), msg).unwrap();
+        let subscription: Subscription = from_json(res).unwrap();
+        assert_eq!(subscription.notified, vec![price("BTC", "99")]);
+
+        let info = message_info(&consumer, &[]);
+        execute(deps.as_mut(), mock_env(), info, ExecuteMsg::Unsubscribe {}).unwrap();
+        let res = update(&mut deps, &admin, vec![price("BTC", "200")]).unwrap();
+        assert!(res.messages.is_empty());
+    }
+
+    #[test]
+    fn query_helpers_read_mock_oracle() {
+        use crate::testing::MockOracle;
+
+        let mut querier = MockQuerier::<Empty>::new(&[]);
+        let oracle = MockOracle::new("oracle")
+            .with_price("btc", "65000".parse().unwrap())
+            .with_price("DAI", Decimal::zero());
+        querier.update_wasm(oracle.handler());
+        let querier = QuerierWrapper::<Empty>::new(&querier);
+        let contract = CwTemplateContract(Addr::unchecked("oracle"));
+
+        let btc = contract.price(&querier, "BTC").unwrap();
+        assert_eq!(btc.price, "65000".parse::<Decimal>().unwrap());
+        assert_eq!(btc.last_updated_height, mock_env().block.height);
+        assert_eq!(contract.prices(&querier).unwrap().prices.len(), 2);
+        assert_eq!(
+            contract.fetch_from_oracle(&querier).unwrap().prices[0].symbol,
+            "BTC"
+        );
+
+        let err = contract.price(&querier, "dai").unwrap_err();
+        assert!(matches!(err, OracleQueryError::PriceNotSet { symbol } if symbol == "DAI"));
+        let err = contract.price_checked(&querier, "DAI").unwrap_err();
+        assert!(matches!(
+            err,
+            OracleQueryError::Query {
+                query: "get_price_checked",
+                ..
+            }
+        ));
+        let err = contract.price(&querier, "ETH").unwrap_err();
+        assert!(err.to_string().contains("Asset ETH is not registered"));
+
+        let other = CwTemplateContract(Addr::unchecked("other"));
+        assert!(other.prices(&querier).is_err());
+    }
+
+    #[test]
+    fn relayed_signed_reports() {
+        use k256::ecdsa::signature::Signer;
+        use k256::ecdsa::{Signature, SigningKey};
+
+        let (mut deps, admin) = setup(vec![]);
+        let relayer = deps.api.addr_make("relayer");
+        let signing_key = SigningKey::from_slice(&[7u8; 32]).unwrap();
+        let pubkey = Binary::from(
+            signing_key
+                .verifying_key()
+                .to_encoded_point(true)
+                .as_bytes()
+                .to_vec(),
+        );
+        let report = |nonce: u64, round_id: u64, timestamp: Timestamp| PriceReport {
+            feeder: admin.to_string(),
+            round_id,
+            nonce,
+            timestamp,
+            prices: vec![price("BTC", "65000")],
+        };
+        let sign = |report: &PriceReport, chain_id: &str| {
+            let bytes = report
+                .sign_bytes(chain_id, &Addr::unchecked(MOCK_CONTRACT_ADDR))
+                .unwrap();
+            let signature: Signature = signing_key.sign(&bytes);
+            Binary::from(signature.to_bytes().to_vec())
+        };
+        let relay = |deps: &mut TorramDeps, env: Env, report: PriceReport, signature| {
+            let msg = ExecuteMsg::SubmitSignedReport { report, signature };
+            execute(deps.as_mut(), env, message_info(&relayer, &[]), msg)
+        };
+        let now = mock_env().block.time;
+        let chain_id = mock_env().block.chain_id;
+
+        let signed = report(1, 1, now);
+        let err = relay(
+            &mut deps,
+            mock_env(),
+            signed.clone(),
+            sign(&signed, &chain_id),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::NoFeederKey { .. }));
+
+        let set_key = |pubkey| ExecuteMsg::SetFeederKey { pubkey };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            message_info(&relayer, &[]),
+            set_key(pubkey.clone()),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::NotFeeder {}));
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            message_info(&admin, &[]),
+            set_key(Binary::from(vec![2u8; 32])),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::InvalidPubkey {}));
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            message_info(&admin, &[]),
+            set_key(pubkey.clone()),
+        )
+        .unwrap();
+
+        // signed for another chain, or tampered with after signing
+        let err = relay(
+            &mut deps,
+            mock_env(),
+            signed.clone(),
+            sign(&signed, "other-chain"),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::InvalidSignature {}));
+        let mut tampered = signed.clone();
+        tampered.prices = vec![price("BTC", "1")];
+        let err = relay(&mut deps, mock_env(), tampered, sign(&signed, &chain_id)).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidSignature {}));
+        let old = report(1, 1, now.minus_seconds(MAX_REPORT_AGE + 1));
+        let err = relay(&mut deps, mock_env(), old.clone(), sign(&old, &chain_id)).unwrap_err();
+        assert!(matches!(err, ContractError::ReportExpired { .. }));
+
+        let res = relay(
+            &mut deps,
+            mock_env(),
+            signed.clone(),
+            sign(&signed, &chain_id),
+        )
+        .unwrap();
+        assert_eq!(attr(&res.events[0], "feeder"), admin.as_str());
+        assert!(res
+            .attributes
+            .contains(&cosmwasm_std::Attribute::new("relayer", relayer.as_str())));
+        let stored = PRICES.load(&deps.storage, "BTC").unwrap();
+        assert_eq!(stored.price, "65000".parse::<Decimal>().unwrap());
+
+        let err = relay(
+            &mut deps,
+            mock_env(),
+            signed.clone(),
+            sign(&signed, &chain_id),
+        )
+        .unwrap_err();
+        assert!(matches!(
+            err,
+            ContractError::NonceReused { last: 1, got: 1 }
+        ));
+        let next = report(2, 2, now);
+        relay(&mut deps, mock_env(), next.clone(), sign(&next, &chain_id)).unwrap();
+
+        let feeders: FeedersResponse =
+            from_json(query(deps.as_ref(), mock_env(), QueryMsg::ListFeeders {}).unwrap()).unwrap();
+        assert_eq!(feeders.feeders[0].pubkey, Some(pubkey));
+    }
+
+    #[test]
+    fn batch_and_paginated_queries() {
+        let (mut deps, admin) = setup(vec![]);
+        update(&mut deps, &admin, vec![price("BTC", "65000")]).unwrap();
+
+        let msg = QueryMsg::GetPricesFor {
+            symbols: vec!["btc".to_string(), "SPX".to_string(), "ETH".to_string()],
+        };
+        let res: PricesForResponse =
+            from_json(query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+        let symbols: Vec<&str> = res.prices.iter().map(|p| p.symbol.as_str()).collect();
+        assert_eq!(symbols, ["BTC", "SPX", "ETH"]);
+        assert_eq!(
+            res.prices[0].price.as_ref().unwrap().price,
+            "65000".parse::<Decimal>().unwrap()
+        );
+        assert_eq!(res.prices[0].error, None);
+        assert_eq!(
+            res.prices[1].error.as_deref(),
+            Some("No price has been set for SPX yet")
+        );
+        assert_eq!(
+            res.prices[2].error.as_deref(),
+            Some("Asset ETH is not registered")
+        );
+        let msg = QueryMsg::GetPricesFor {
+            symbols: vec!["BTC".to_string(); 101],
+        };
+        let err = query(deps.as_ref(), mock_env(), msg).unwrap_err();
+        assert!(matches!(err, ContractError::TooManySymbols { max: 100 }));
+
+        let list = |start_after: Option<&str>, limit| {
+            let msg = QueryMsg::ListAssets {
+                start_after: start_after.map(str::to_string),
+                limit,
+            };
+            let res: AssetsResponse =
+                from_json(query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+            res.assets.into_iter().map(|a| a.symbol).collect::<Vec<_>>()
+        };
+        assert_eq!(list(None, None), ["BTC", "SPX", "USDC"]);
+        assert_eq!(list(None, Some(2)), ["BTC", "SPX"]);
+        assert_eq!(list(Some("spx"), Some(2)), ["USDC"]);
+    }
+
+    #[test]
+    fn torram_queries_keep_the_method_args_wire_format() {
+        let wire = |query: TssConsensusQuery| {
+            String::from_utf8(to_json_vec(&QueryRequest::Custom(TorramQuery::from(query))).unwrap())
+                .unwrap()
+        };
+        assert_eq!(
+            wire(TssConsensusQuery::GetAllContacts {}),
+            r#"{"custom":{"method":"get_all_contacts","args":{}}}"#
+        );
+        assert_eq!(
+            wire(TssConsensusQuery::GetContact {
+                address: "torram1abc".to_string()
+            }),
+            r#"{"custom":{"method":"get_contact","args":{"address":"torram1abc"}}}"#
+        );
+        assert_eq!(
+            wire(TssConsensusQuery::GetPricesAtHeight { height: 42 }),
+            r#"{"custom":{"method":"get_prices_at_height","args":{"height":42}}}"#
+        );
+        let parsed: TorramQuery =
+            from_json(br#"{"method":"get_prices_at_height","args":{"height":42}}"#).unwrap();
+        assert_eq!(
+            parsed,
+            TssConsensusQuery::GetPricesAtHeight { height: 42 }.into()
+        );
+    }
+
+    #[test]
+    fn raw_feeds_store_recognised_symbols() {
+        let (mut deps, admin) = setup(vec![]);
+        let raw = |deps: &mut TorramDeps, feed: &str, source_height| {
+            let msg = ExecuteMsg::UpdatePricesRaw {
+                feed: feed.to_string(),
+                source_height,
+            };
+            execute(deps.as_mut(), mock_env(), message_info(&admin, &[]), msg)
+        };
+        let height = mock_env().block.height;
+
+        let err = raw(&mut deps, "BTC:100", height + 1).unwrap_err();
+        assert!(matches!(err, ContractError::FutureSourceHeight { .. }));
+        let err = raw(&mut deps, "BTC:100,BTC:101", height).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Invalid price feed: Duplicate symbol BTC at byte 8"
+        );
+        let err = raw(&mut deps, "ETH:2627.48", height).unwrap_err();
+        assert!(matches!(err, ContractError::EmptyPrices {}));
+
+        let res = raw(
+            &mut deps,
+            "BTC:109290.5, ETH:2627.48, usdc:0.9999",
+            height - 5,
+        )
+        .unwrap();
+        assert!(res
+            .attributes
+            .contains(&cosmwasm_std::Attribute::new("ignored", "ETH")));
+        assert!(res.attributes.contains(&cosmwasm_std::Attribute::new(
+            "source_height",
+            (height - 5).to_string()
+        )));
+        assert_eq!(attr(&res.events[0], "btc"), "109290.5");
+        assert_eq!(attr(&res.events[0], "usdc"), "0.9999");
+        let stored = PRICES.load(&deps.storage, "USDC").unwrap();
+        assert_eq!(stored.price, "0.9999".parse::<Decimal>().unwrap());
     }
 }
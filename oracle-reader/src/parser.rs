@@ -0,0 +1,382 @@
+//! Parser for the price list carried in a `tssconsensus` contact.
+//!
+//! A contact string is a comma-separated list of `SYMBOL:PRICE` entries, e.g.
+//! `BTC:109290.5, ETH:2627.48`. Whitespace around tokens is ignored and either
+//! token may be wrapped in single or double quotes. Symbols are normalized like
+//! registered assets, so `btc` and `"BTC"` name the same entry. An empty or
+//! blank string is an empty list.
+//!
+//! Every error carries the byte offset in the contact string where the
+//! offending token starts.
+
+use crate::normalize_symbol;
+use cosmwasm_std::Decimal;
+use std::collections::BTreeMap;
+use std::str::FromStr;
+use thiserror::Error;
+
+/// Upper-case asset symbol, as produced by `normalize_symbol`.
+pub type Symbol = String;
+
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum ParseError {
+    #[error("Expected {expected} at byte {pos}")]
+    Expected { expected: &'static str, pos: usize },
+
+    #[error("Unterminated quote at byte {pos}")]
+    UnterminatedQuote { pos: usize },
+
+    #[error("Invalid symbol {symbol:?} at byte {pos}")]
+    InvalidSymbol { symbol: String, pos: usize },
+
+    #[error("Duplicate symbol {symbol} at byte {pos}")]
+    DuplicateSymbol { symbol: Symbol, pos: usize },
+
+    #[error("Negative price {value:?} for {symbol} at byte {pos}")]
+    NegativePrice {
+        symbol: Symbol,
+        value: String,
+        pos: usize,
+    },
+
+    #[error("Invalid price {value:?} for {symbol} at byte {pos}")]
+    InvalidPrice {
+        symbol: Symbol,
+        value: String,
+        pos: usize,
+    },
+}
+
+/// Parses a whole contact string into its prices, keyed by symbol.
+pub fn parse_prices(input: &str) -> Result<BTreeMap<Symbol, Decimal>, ParseError> {
+    let mut cursor = Cursor { input, pos: 0 };
+    let mut prices = BTreeMap::new();
+    cursor.skip_whitespace();
+    if cursor.at_end() {
+        return Ok(prices);
+    }
+    loop {
+        let (raw_symbol, symbol_pos) = cursor.token("symbol")?;
+        let symbol = normalize_symbol(raw_symbol).map_err(|_| ParseError::InvalidSymbol {
+            symbol: raw_symbol.to_string(),
+            pos: symbol_pos,
+        })?;
+        cursor.expect(b':', "':'")?;
+        let (value, value_pos) = cursor.token("price")?;
+        let price = parse_price(&symbol, value, value_pos)?;
+        if prices.contains_key(&symbol) {
+            return Err(ParseError::DuplicateSymbol {
+                symbol,
+                pos: symbol_pos,
+            });
+        }
+        prices.insert(symbol, price);
+
+        cursor.skip_whitespace();
+        if cursor.at_end() {
+            return Ok(prices);
+        }
+        cursor.expect(b',', "',' or end of input")?;
+    }
+}
+
+fn parse_price(symbol: &str, value: &str, pos: usize) -> Result<Decimal, ParseError> {
+    if value.starts_with('-') {
+        return Err(ParseError::NegativePrice {
+            symbol: symbol.to_string(),
+            value: value.to_string(),
+            pos,
+        });
+    }
+    Decimal::from_str(value).map_err(|_| ParseError::InvalidPrice {
+        symbol: symbol.to_string(),
+        value: value.to_string(),
+        pos,
+    })
+}
+
+struct Cursor<'a> {
+    input: &'a str,
+    pos: usize,
+}
+
+impl<'a> Cursor<'a> {
+    fn at_end(&self) -> bool {
+        self.pos == self.input.len()
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.input.as_bytes().get(self.pos).copied()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(|b| b.is_ascii_whitespace()) {
+            self.pos += 1;
+        }
+    }
+
+    fn expect(&mut self, byte: u8, expected: &'static str) -> Result<(), ParseError> {
+        self.skip_whitespace();
+        if self.peek() != Some(byte) {
+            return Err(ParseError::Expected {
+                expected,
+                pos: self.pos,
+            });
+        }
+        self.pos += 1;
+        Ok(())
+    }
+
+    /// Reads a quoted or bare token and returns it with its start offset. A
+    /// bare token runs up to whitespace, a separator or a quote.
+    fn token(&mut self, expected: &'static str) -> Result<(&'a str, usize), ParseError> {
+        self.skip_whitespace();
+        let start = self.pos;
+        if let Some(quote @ (b'"' | b'\'')) = self.peek() {
+            let content = &self.input[start + 1..];
+            let len = content
+                .bytes()
+                .position(|b| b == quote)
+                .ok_or(ParseError::UnterminatedQuote { pos: start })?;
+            self.pos = start + 1 + len + 1;
+            return Ok((&content[..len], start));
+        }
+        while self
+            .peek()
+            .is_some_and(|b| !b.is_ascii_whitespace() && !matches!(b, b',' | b':' | b'"' | b'\''))
+        {
+            self.pos += 1;
+        }
+        if self.pos == start {
+            return Err(ParseError::Expected {
+                expected,
+                pos: start,
+            });
+        }
+        Ok((&self.input[start..self.pos], start))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    fn dec(value: &str) -> Decimal {
+        value.parse().unwrap()
+    }
+
+    fn parsed(input: &str) -> Vec<(Symbol, Decimal)> {
+        parse_prices(input).unwrap().into_iter().collect()
+    }
+
+    #[test]
+    fn parses_plain_quoted_and_spaced_entries() {
+        assert_eq!(
+            parsed("BTC:109290.5,ETH:2627.48"),
+            [
+                ("BTC".to_string(), dec("109290.5")),
+                ("ETH".to_string(), dec("2627.48"))
+            ]
+        );
+        assert_eq!(
+            parsed(" \"btc\" : '100' ,\n\tUSDC:\"0.9999\"  "),
+            [
+                ("BTC".to_string(), dec("100")),
+                ("USDC".to_string(), dec("0.9999"))
+            ]
+        );
+        assert_eq!(parsed("WETH:1,ETH:2")[1], ("WETH".to_string(), dec("1")));
+        assert_eq!(parsed("DAI:0"), [("DAI".to_string(), Decimal::zero())]);
+        assert!(parsed("").is_empty());
+        assert!(parsed("  \n").is_empty());
+    }
+
+    #[test]
+    fn rejects_malformed_entries_with_positions() {
+        let err = |input| parse_prices(input).unwrap_err();
+        assert_eq!(
+            err("BTC:1,BTC:2"),
+            ParseError::DuplicateSymbol {
+                symbol: "BTC".to_string(),
+                pos: 6
+            }
+        );
+        assert_eq!(
+            err("BTC:1, btc:2"),
+            ParseError::DuplicateSymbol {
+                symbol: "BTC".to_string(),
+                pos: 7
+            }
+        );
+        assert_eq!(
+            err("BTC:-1"),
+            ParseError::NegativePrice {
+                symbol: "BTC".to_string(),
+                value: "-1".to_string(),
+                pos: 4
+            }
+        );
+        assert_eq!(
+            err("BTC:1,USDC:oops"),
+            ParseError::InvalidPrice {
+                symbol: "USDC".to_string(),
+                value: "oops".to_string(),
+                pos: 11
+            }
+        );
+        assert_eq!(
+            err("BTC:1,garbage"),
+            ParseError::Expected {
+                expected: "':'",
+                pos: 13
+            }
+        );
+        assert_eq!(
+            err("BTC:1,"),
+            ParseError::Expected {
+                expected: "symbol",
+                pos: 6
+            }
+        );
+        assert_eq!(
+            err("BTC:"),
+            ParseError::Expected {
+                expected: "price",
+                pos: 4
+            }
+        );
+        assert_eq!(
+            err("BTC:1 ETH:2"),
+            ParseError::Expected {
+                expected: "',' or end of input",
+                pos: 6
+            }
+        );
+        assert_eq!(
+            err("BTC:1;ETH:2"),
+            ParseError::InvalidPrice {
+                symbol: "BTC".to_string(),
+                value: "1;ETH".to_string(),
+                pos: 4
+            }
+        );
+        assert_eq!(err("BTC:\"1"), ParseError::UnterminatedQuote { pos: 4 });
+        assert_eq!(
+            err("W-ETH:1"),
+            ParseError::InvalidSymbol {
+                symbol: "W-ETH".to_string(),
+                pos: 0
+            }
+        );
+        assert_eq!(
+            err("\"\":1"),
+            ParseError::InvalidSymbol {
+                symbol: String::new(),
+                pos: 0
+            }
+        );
+        assert_eq!(
+            err("BTC:1e5").to_string(),
+            "Invalid price \"1e5\" for BTC at byte 4"
+        );
+    }
+
+    fn symbol() -> impl Strategy<Value = Symbol> {
+        "[A-Z0-9]{1,12}"
+    }
+
+    fn price() -> impl Strategy<Value = Decimal> {
+        (any::<u64>(), 0u32..=18)
+            .prop_map(|(atomics, places)| Decimal::from_atomics(atomics, places).unwrap())
+    }
+
+    fn quoted(token: String, style: u8) -> String {
+        match style {
+            0 => token,
+            1 => format!("\"{token}\""),
+            _ => format!("'{token}'"),
+        }
+    }
+
+    fn space() -> impl Strategy<Value = &'static str> {
+        prop::sample::select(vec!["", " ", "  ", "\t", "\n "])
+    }
+
+    proptest! {
+        #[test]
+        fn formatted_lists_round_trip(
+            prices in prop::collection::btree_map(symbol(), price(), 0..8),
+            styles in prop::collection::vec((0u8..3, 0u8..3, any::<bool>()), 8),
+            spaces in prop::collection::vec(space(), 40),
+        ) {
+            let mut spaces = spaces.into_iter().cycle();
+            let entries: Vec<String> = prices
+                .iter()
+                .zip(&styles)
+                .map(|((symbol, price), &(symbol_style, price_style, lower))| {
+                    let symbol = if lower { symbol.to_ascii_lowercase() } else { symbol.clone() };
+                    format!(
+                        "{}{}{}:{}{}{}",
+                        spaces.next().unwrap(),
+                        quoted(symbol, symbol_style),
+                        spaces.next().unwrap(),
+                        spaces.next().unwrap(),
+                        quoted(price.to_string(), price_style),
+                        spaces.next().unwrap(),
+                    )
+                })
+                .collect();
+            prop_assert_eq!(parse_prices(&entries.join(",")), Ok(prices));
+        }
+
+        #[test]
+        fn repeated_symbols_are_rejected(
+            prices in prop::collection::btree_map(symbol(), price(), 1..6),
+            repeat in any::<prop::sample::Index>(),
+        ) {
+            let prices: Vec<(Symbol, Decimal)> = prices.into_iter().collect();
+            let (symbol, price) = repeat.get(&prices).clone();
+            let mut entries: Vec<String> =
+                prices.iter().map(|(s, p)| format!("{s}:{p}")).collect();
+            entries.push(format!("{}:{price}", symbol.to_ascii_lowercase()));
+            let input = entries.join(",");
+            prop_assert_eq!(
+                parse_prices(&input),
+                Err(ParseError::DuplicateSymbol {
+                    symbol,
+                    pos: input.rfind(',').unwrap() + 1,
+                })
+            );
+        }
+
+        #[test]
+        fn negative_prices_are_rejected(symbol in symbol(), price in price()) {
+            let input = format!("{symbol}:-{price}");
+            prop_assert_eq!(
+                parse_prices(&input),
+                Err(ParseError::NegativePrice {
+                    value: format!("-{price}"),
+                    symbol,
+                    pos: input.find(':').unwrap() + 1,
+                })
+            );
+        }
+
+        #[test]
+        fn arbitrary_input_never_panics(input in "\\PC*") {
+            if let Err(err) = parse_prices(&input) {
+                let pos = match err {
+                    ParseError::Expected { pos, .. }
+                    | ParseError::UnterminatedQuote { pos }
+                    | ParseError::InvalidSymbol { pos, .. }
+                    | ParseError::DuplicateSymbol { pos, .. }
+                    | ParseError::NegativePrice { pos, .. }
+                    | ParseError::InvalidPrice { pos, .. } => pos,
+                };
+                prop_assert!(pos <= input.len());
+                prop_assert!(input.is_char_boundary(pos));
+            }
+        }
+    }
+}
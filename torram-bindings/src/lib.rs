@@ -0,0 +1,537 @@
+//! Custom queries served by Torram chain modules, shared by every contract
+//! that reads them.
+//!
+//! `TSBQuery` covers the token sync (TSB) module and `TssConsensusQuery` the
+//! `tssconsensus` oracle module. `TorramQuery` combines both into a single
+//! `CustomQuery`, and `TorramQuerier` adds typed methods for it to
+//! `QuerierWrapper`.
+
+use cosmwasm_std::{CustomQuery, QuerierWrapper, QueryRequest, StdResult};
+use schemars::JsonSchema;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+// TSB query types that match the Go bindings
+//
+// The list queries are paginated: pass the `next_key` of a response as
+// `start_after` to read the following page. The key format is up to the
+// module, so treat it as opaque. Omitted pagination fields are left out of
+// the request, which then reads the module's default page as before.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum TSBQuery {
+    GetAllTokens {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        start_after: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        limit: Option<u32>,
+    },
+    GetToken {
+        token_id: String,
+    },
+    GetTokensByCreator {
+        creator: String,
+    },
+    GetAllBalances {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        start_after: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        limit: Option<u32>,
+    },
+    GetTokenBalance {
+        token_id: String,
+        owner: String,
+    },
+    GetBalancesByOwner {
+        owner: String,
+    },
+    GetTokenOperations {
+        token_id: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        start_after: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        limit: Option<u32>,
+    },
+    GetTokenOperation {
+        operation_id: String,
+    },
+    GetPendingBitcoinSync {},
+    GetTokensForSync {},
+    GetUtxos {
+        address: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        start_after: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        limit: Option<u32>,
+    },
+    // Counters kept by the module, so that callers need not scan the lists
+    GetTokenStats {
+        token_id: String,
+    },
+    GetHolderCount {
+        token_id: String,
+    },
+    GetSyncCounters {},
+}
+
+impl CustomQuery for TSBQuery {}
+
+impl TSBQuery {
+    /// Method name on the wire, used in error messages.
+    pub fn method(&self) -> &'static str {
+        match self {
+            TSBQuery::GetAllTokens { .. } => "get_all_tokens",
+            TSBQuery::GetToken { .. } => "get_token",
+            TSBQuery::GetTokensByCreator { .. } => "get_tokens_by_creator",
+            TSBQuery::GetAllBalances { .. } => "get_all_balances",
+            TSBQuery::GetTokenBalance { .. } => "get_token_balance",
+            TSBQuery::GetBalancesByOwner { .. } => "get_balances_by_owner",
+            TSBQuery::GetTokenOperations { .. } => "get_token_operations",
+            TSBQuery::GetTokenOperation { .. } => "get_token_operation",
+            TSBQuery::GetPendingBitcoinSync {} => "get_pending_bitcoin_sync",
+            TSBQuery::GetTokensForSync {} => "get_tokens_for_sync",
+            TSBQuery::GetUtxos { .. } => "get_utxos",
+            TSBQuery::GetTokenStats { .. } => "get_token_stats",
+            TSBQuery::GetHolderCount { .. } => "get_holder_count",
+            TSBQuery::GetSyncCounters {} => "get_sync_counters",
+        }
+    }
+
+    /// The `start_after` and `limit` of a paginated query, or `None` for the
+    /// others.
+    pub fn page(&self) -> Option<(Option<&str>, Option<u32>)> {
+        match self {
+            TSBQuery::GetAllTokens { start_after, limit }
+            | TSBQuery::GetAllBalances { start_after, limit }
+            | TSBQuery::GetTokenOperations {
+                start_after, limit, ..
+            }
+            | TSBQuery::GetUtxos {
+                start_after, limit, ..
+            } => Some((start_after.as_deref(), *limit)),
+            _ => None,
+        }
+    }
+}
+
+// TSB response types
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct TSBToken {
+    pub token_id: String,
+    pub amount: String,
+    pub type_code: u32,
+    pub metadata: String,
+    pub creator: String,
+    pub creation_time: String,
+    pub bitcoin_tx_id: String,
+    pub synced_with_bitcoin: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct TSBBalance {
+    pub token_id: String,
+    pub owner: String,
+    pub amount: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct TSBOperation {
+    pub operation_id: String,
+    pub token_id: String,
+    pub r#type: u32,
+    pub from: String,
+    pub to: String,
+    pub amount: String,
+    pub timestamp: String,
+    pub bitcoin_tx_id: String,
+    pub torram_tx_id: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct TSBCTXO {
+    pub tx_id: String,
+    pub vout: u32,
+    pub amount: String,
+    pub used: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct GetAllTokensResponse {
+    pub tokens: Vec<TSBToken>,
+    /// `start_after` for the next page, `None` on the last one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub next_key: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct GetTokenResponse {
+    pub token: Option<TSBToken>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct GetTokensByCreatorResponse {
+    pub tokens: Vec<TSBToken>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct GetAllBalancesResponse {
+    pub balances: Vec<TSBBalance>,
+    /// `start_after` for the next page, `None` on the last one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub next_key: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct GetTokenBalanceResponse {
+    pub balance: Option<TSBBalance>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct GetBalancesByOwnerResponse {
+    pub balances: Vec<TSBBalance>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct GetTokenOperationsResponse {
+    pub operations: Vec<TSBOperation>,
+    /// `start_after` for the next page, `None` on the last one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub next_key: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct GetTokenOperationResponse {
+    pub operation: Option<TSBOperation>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct GetPendingBitcoinSyncResponse {
+    pub operations: Vec<TSBOperation>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct GetTokensForSyncResponse {
+    pub token_ids: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct GetUTXOsResponse {
+    pub utxos: Vec<TSBCTXO>,
+    /// `start_after` for the next page, `None` on the last one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub next_key: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct GetTokenStatsResponse {
+    pub token_id: String,
+    pub operations_count: u32,
+    /// Operations of the token still waiting for their Bitcoin sync.
+    pub pending_operations: u32,
+}
+
+/// Owners holding a non-zero balance of the token.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct GetHolderCountResponse {
+    pub token_id: String,
+    pub holder_count: u32,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct GetSyncCountersResponse {
+    pub total_tokens: u32,
+    pub synced_tokens: u32,
+    pub pending_operations: u32,
+}
+
+/// Queries served by the `tssconsensus` module.
+///
+/// Serializes as `{"method": "<snake_case name>", "args": {...}}`, the format
+/// the chain's bindings expect.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(tag = "method", content = "args", rename_all = "snake_case")]
+pub enum TssConsensusQuery {
+    /// Returns `ContactsResponse`.
+    GetAllContacts {},
+    /// Returns `ContactResponse`.
+    GetContact { address: String },
+    /// Returns `PricesAtHeightResponse`.
+    GetPricesAtHeight { height: u64 },
+}
+
+impl CustomQuery for TssConsensusQuery {}
+
+/// A validator's oracle report; `contact` holds its `SYMBOL:PRICE` list.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Contact {
+    pub address: String,
+    pub contact: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ContactsResponse {
+    pub contacts: Vec<Contact>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ContactResponse {
+    pub contact: Contact,
+}
+
+/// Contacts as they were at `height`, with the prices they reported then.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PricesAtHeightResponse {
+    pub height: u64,
+    pub contacts: Vec<Contact>,
+}
+
+/// Any Torram custom query. Each variant serializes exactly like the query it
+/// wraps, so the chain sees the same request as from a single-module contract.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(untagged)]
+pub enum TorramQuery {
+    Tsb(TSBQuery),
+    TssConsensus(TssConsensusQuery),
+}
+
+impl CustomQuery for TorramQuery {}
+
+impl From<TSBQuery> for TorramQuery {
+    fn from(query: TSBQuery) -> Self {
+        TorramQuery::Tsb(query)
+    }
+}
+
+impl From<TssConsensusQuery> for TorramQuery {
+    fn from(query: TssConsensusQuery) -> Self {
+        TorramQuery::TssConsensus(query)
+    }
+}
+
+/// Typed access to every `TorramQuery`, e.g. `deps.querier.all_tokens()`.
+pub trait TorramQuerier {
+    fn torram_query<T: DeserializeOwned>(&self, query: impl Into<TorramQuery>) -> StdResult<T>;
+
+    fn all_tokens(
+        &self,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    ) -> StdResult<GetAllTokensResponse> {
+        self.torram_query(TSBQuery::GetAllTokens { start_after, limit })
+    }
+
+    fn token(&self, token_id: impl Into<String>) -> StdResult<GetTokenResponse> {
+        let token_id = token_id.into();
+        self.torram_query(TSBQuery::GetToken { token_id })
+    }
+
+    fn tokens_by_creator(
+        &self,
+        creator: impl Into<String>,
+    ) -> StdResult<GetTokensByCreatorResponse> {
+        let creator = creator.into();
+        self.torram_query(TSBQuery::GetTokensByCreator { creator })
+    }
+
+    fn all_balances(
+        &self,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    ) -> StdResult<GetAllBalancesResponse> {
+        self.torram_query(TSBQuery::GetAllBalances { start_after, limit })
+    }
+
+    fn token_balance(
+        &self,
+        token_id: impl Into<String>,
+        owner: impl Into<String>,
+    ) -> StdResult<GetTokenBalanceResponse> {
+        self.torram_query(TSBQuery::GetTokenBalance {
+            token_id: token_id.into(),
+            owner: owner.into(),
+        })
+    }
+
+    fn balances_by_owner(&self, owner: impl Into<String>) -> StdResult<GetBalancesByOwnerResponse> {
+        let owner = owner.into();
+        self.torram_query(TSBQuery::GetBalancesByOwner { owner })
+    }
+
+    fn token_operations(
+        &self,
+        token_id: impl Into<String>,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    ) -> StdResult<GetTokenOperationsResponse> {
+        self.torram_query(TSBQuery::GetTokenOperations {
+            token_id: token_id.into(),
+            start_after,
+            limit,
+        })
+    }
+
+    fn token_operation(
+        &self,
+        operation_id: impl Into<String>,
+    ) -> StdResult<GetTokenOperationResponse> {
+        let operation_id = operation_id.into();
+        self.torram_query(TSBQuery::GetTokenOperation { operation_id })
+    }
+
+    fn pending_bitcoin_sync(&self) -> StdResult<GetPendingBitcoinSyncResponse> {
+        self.torram_query(TSBQuery::GetPendingBitcoinSync {})
+    }
+
+    fn tokens_for_sync(&self) -> StdResult<GetTokensForSyncResponse> {
+        self.torram_query(TSBQuery::GetTokensForSync {})
+    }
+
+    /// UTXOs of `address`, or of every address when `None`.
+    fn utxos(
+        &self,
+        address: Option<String>,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    ) -> StdResult<GetUTXOsResponse> {
+        self.torram_query(TSBQuery::GetUtxos {
+            address,
+            start_after,
+            limit,
+        })
+    }
+
+    fn token_stats(&self, token_id: impl Into<String>) -> StdResult<GetTokenStatsResponse> {
+        let token_id = token_id.into();
+        self.torram_query(TSBQuery::GetTokenStats { token_id })
+    }
+
+    fn holder_count(&self, token_id: impl Into<String>) -> StdResult<GetHolderCountResponse> {
+        let token_id = token_id.into();
+        self.torram_query(TSBQuery::GetHolderCount { token_id })
+    }
+
+    fn sync_counters(&self) -> StdResult<GetSyncCountersResponse> {
+        self.torram_query(TSBQuery::GetSyncCounters {})
+    }
+
+    fn all_contacts(&self) -> StdResult<ContactsResponse> {
+        self.torram_query(TssConsensusQuery::GetAllContacts {})
+    }
+
+    fn contact(&self, address: impl Into<String>) -> StdResult<ContactResponse> {
+        let address = address.into();
+        self.torram_query(TssConsensusQuery::GetContact { address })
+    }
+
+    fn prices_at_height(&self, height: u64) -> StdResult<PricesAtHeightResponse> {
+        self.torram_query(TssConsensusQuery::GetPricesAtHeight { height })
+    }
+}
+
+impl TorramQuerier for QuerierWrapper<'_, TorramQuery> {
+    fn torram_query<T: DeserializeOwned>(&self, query: impl Into<TorramQuery>) -> StdResult<T> {
+        self.query(&QueryRequest::Custom(query.into()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cosmwasm_std::testing::MockQuerier;
+    use cosmwasm_std::{from_json, to_json_binary, to_json_vec, ContractResult, SystemResult};
+
+    fn wire(query: impl Into<TorramQuery>) -> String {
+        String::from_utf8(to_json_vec(&QueryRequest::Custom(query.into())).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn combined_queries_keep_each_module_wire_format() {
+        assert_eq!(
+            wire(TSBQuery::GetToken {
+                token_id: "token_1".to_string()
+            }),
+            r#"{"custom":{"get_token":{"token_id":"token_1"}}}"#
+        );
+        assert_eq!(
+            wire(TssConsensusQuery::GetAllContacts {}),
+            r#"{"custom":{"method":"get_all_contacts","args":{}}}"#
+        );
+        assert_eq!(
+            wire(TssConsensusQuery::GetPricesAtHeight { height: 42 }),
+            r#"{"custom":{"method":"get_prices_at_height","args":{"height":42}}}"#
+        );
+
+        let parsed: TorramQuery = from_json(br#"{"get_utxos":{"address":null}}"#).unwrap();
+        assert_eq!(
+            parsed,
+            TSBQuery::GetUtxos {
+                address: None,
+                start_after: None,
+                limit: None
+            }
+            .into()
+        );
+        let parsed: TorramQuery =
+            from_json(br#"{"method":"get_contact","args":{"address":"torram1abc"}}"#).unwrap();
+        assert_eq!(
+            parsed,
+            TssConsensusQuery::GetContact {
+                address: "torram1abc".to_string()
+            }
+            .into()
+        );
+    }
+
+    #[test]
+    fn pagination_fields_are_optional_on_the_wire() {
+        let unpaged = TSBQuery::GetAllTokens {
+            start_after: None,
+            limit: None,
+        };
+        assert_eq!(wire(unpaged), r#"{"custom":{"get_all_tokens":{}}}"#);
+        assert_eq!(
+            wire(TSBQuery::GetTokenOperations {
+                token_id: "token_1".to_string(),
+                start_after: Some("op_9".to_string()),
+                limit: Some(20),
+            }),
+            r#"{"custom":{"get_token_operations":{"token_id":"token_1","start_after":"op_9","limit":20}}}"#
+        );
+
+        // responses from modules without pagination are a single last page
+        let res: GetAllBalancesResponse = from_json(br#"{"balances":[]}"#).unwrap();
+        assert_eq!(res.next_key, None);
+        let res: GetUTXOsResponse = from_json(br#"{"utxos":[],"next_key":"tx_a:1"}"#).unwrap();
+        assert_eq!(res.next_key.as_deref(), Some("tx_a:1"));
+    }
+
+    #[test]
+    fn querier_answers_both_modules() {
+        let querier = MockQuerier::<TorramQuery>::new(&[]).with_custom_handler(|query| {
+            let response = match query {
+                TorramQuery::Tsb(TSBQuery::GetTokensForSync {}) => {
+                    to_json_binary(&GetTokensForSyncResponse {
+                        token_ids: vec!["token_1".to_string()],
+                    })
+                }
+                TorramQuery::TssConsensus(TssConsensusQuery::GetAllContacts {}) => {
+                    to_json_binary(&ContactsResponse {
+                        contacts: vec![Contact {
+                            address: "torram1abc".to_string(),
+                            contact: "BTC:100".to_string(),
+                        }],
+                    })
+                }
+                _ => panic!("unexpected query {:?}", query),
+            };
+            SystemResult::Ok(ContractResult::Ok(response.unwrap()))
+        });
+        let querier = QuerierWrapper::<TorramQuery>::new(&querier);
+
+        assert_eq!(querier.tokens_for_sync().unwrap().token_ids, ["token_1"]);
+        assert_eq!(
+            querier.all_contacts().unwrap().contacts[0].contact,
+            "BTC:100"
+        );
+    }
+}
@@ -1,15 +1,56 @@
 #[cfg(test)]
 mod tests {
-    use super::*;
-    use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
-    use cosmwasm_std::{coins, from_binary};
+    use crate::testing::{
+        for_each_page, mock_operation, mock_token, mock_tsb_dependencies, mock_utxo, MockTsbDeps,
+        MockTsbQuerier,
+    };
+    use crate::*;
+    use cosmwasm_std::testing::{message_info, mock_env};
+    use cosmwasm_std::{coins, from_json, Empty, QuerierWrapper};
+    use serde::de::DeserializeOwned;
+
+    /// Two tokens, three holders, one pending transfer and a few UTXOs.
+    fn fixture() -> MockTsbDeps {
+        let mut synced = mock_token("token_2", "torram1creator", "50");
+        synced.synced_with_bitcoin = true;
+        let querier = MockTsbQuerier::default()
+            .with_token(mock_token("token_1", "torram1creator", "1000"))
+            .with_token(synced)
+            .with_balance("token_1", "torram1alice", "600")
+            .with_balance("token_1", "torram1bob", "400")
+            .with_balance("token_1", "torram1carol", "0")
+            .with_balance("token_2", "torram1alice", "50")
+            .with_operation(mock_operation(
+                "op_1",
+                "token_1",
+                "torram1creator",
+                "torram1alice",
+                "1000",
+            ))
+            .with_pending_operation(mock_operation(
+                "op_2",
+                "token_1",
+                "torram1alice",
+                "torram1bob",
+                "400",
+            ))
+            .with_token_for_sync("token_1")
+            .with_utxo("bc1alice", mock_utxo("tx_a", 0, "0.5"))
+            .with_utxo("bc1alice", mock_utxo("tx_b", 1, "0.25"))
+            .with_utxo("bc1bob", mock_utxo("tx_c", 0, "1"));
+        mock_tsb_dependencies(querier)
+    }
+
+    fn run<T: DeserializeOwned>(deps: &MockTsbDeps, msg: QueryMsg) -> T {
+        from_json(query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap()
+    }
 
     #[test]
     fn proper_initialization() {
-        let mut deps = mock_dependencies();
+        let mut deps = fixture();
 
         let msg = InstantiateMsg {};
-        let info = mock_info("creator", &coins(1000, "earth"));
+        let info = message_info(&deps.api.addr_make("creator"), &coins(1000, "earth"));
 
         // we can just call .unwrap() to assert this was a success
         let res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
@@ -18,51 +59,499 @@ mod tests {
 
     #[test]
     fn test_query_all_tokens() {
-        let deps = mock_dependencies();
+        let deps = fixture();
+
+        let res: GetAllTokensResponse = run(
+            &deps,
+            QueryMsg::GetAllTokens {
+                start_after: None,
+                limit: None,
+            },
+        );
+        let ids: Vec<&str> = res.tokens.iter().map(|t| t.token_id.as_str()).collect();
+        assert_eq!(ids, ["token_1", "token_2"]);
+    }
+
+    #[test]
+    fn test_query_token() {
+        let deps = fixture();
+
+        let res: GetTokenResponse = run(
+            &deps,
+            QueryMsg::GetToken {
+                token_id: "token_1".to_string(),
+            },
+        );
+        assert_eq!(res.token.unwrap().amount, "1000");
+        let res: GetTokenResponse = run(
+            &deps,
+            QueryMsg::GetToken {
+                token_id: "missing".to_string(),
+            },
+        );
+        assert_eq!(res.token, None);
+
+        let res: GetTokensByCreatorResponse = run(
+            &deps,
+            QueryMsg::GetTokensByCreator {
+                creator: "torram1creator".to_string(),
+            },
+        );
+        assert_eq!(res.tokens.len(), 2);
+    }
+
+    #[test]
+    fn test_query_balances() {
+        let deps = fixture();
+
+        let res: GetAllBalancesResponse = run(
+            &deps,
+            QueryMsg::GetAllBalances {
+                start_after: None,
+                limit: None,
+            },
+        );
+        assert_eq!(res.balances.len(), 4);
+
+        let res: GetTokenBalanceResponse = run(
+            &deps,
+            QueryMsg::GetTokenBalance {
+                token_id: "token_1".to_string(),
+                owner: "torram1bob".to_string(),
+            },
+        );
+        assert_eq!(res.balance.unwrap().amount, "400");
+
+        let res: GetBalancesByOwnerResponse = run(
+            &deps,
+            QueryMsg::GetBalancesByOwner {
+                owner: "torram1alice".to_string(),
+            },
+        );
+        let tokens: Vec<&str> = res.balances.iter().map(|b| b.token_id.as_str()).collect();
+        assert_eq!(tokens, ["token_1", "token_2"]);
+    }
+
+    #[test]
+    fn test_query_operations() {
+        let deps = fixture();
+
+        let res: GetTokenOperationsResponse = run(
+            &deps,
+            QueryMsg::GetTokenOperations {
+                token_id: "token_1".to_string(),
+                start_after: None,
+                limit: None,
+            },
+        );
+        assert_eq!(res.operations.len(), 2);
+
+        let res: GetTokenOperationResponse = run(
+            &deps,
+            QueryMsg::GetTokenOperation {
+                operation_id: "op_2".to_string(),
+            },
+        );
+        assert_eq!(res.operation.unwrap().to, "torram1bob");
+
+        let res: GetPendingBitcoinSyncResponse = run(&deps, QueryMsg::GetPendingBitcoinSync {});
+        let ids: Vec<&str> = res
+            .operations
+            .iter()
+            .map(|op| op.operation_id.as_str())
+            .collect();
+        assert_eq!(ids, ["op_2"]);
+
+        let res: GetTokensForSyncResponse = run(&deps, QueryMsg::GetTokensForSync {});
+        assert_eq!(res.token_ids, ["token_1"]);
+    }
 
-        let msg = QueryMsg::GetAllTokens {};
-        let res = query(deps.as_ref(), mock_env(), msg);
-        
-        // In a real test, this would succeed with mocked TSB data
-        // For now, we just verify the query structure is correct
-        assert!(res.is_err()); // Expected to fail in mock environment
+    #[test]
+    fn test_query_utxos() {
+        let deps = fixture();
+
+        let res: GetUTXOsResponse = run(
+            &deps,
+            QueryMsg::GetUtxos {
+                address: Some("bc1alice".to_string()),
+                start_after: None,
+                limit: None,
+            },
+        );
+        let txs: Vec<&str> = res.utxos.iter().map(|u| u.tx_id.as_str()).collect();
+        assert_eq!(txs, ["tx_a", "tx_b"]);
+
+        let res: GetUTXOsResponse = run(
+            &deps,
+            QueryMsg::GetUtxos {
+                address: None,
+                start_after: None,
+                limit: None,
+            },
+        );
+        assert_eq!(res.utxos.len(), 3);
     }
 
     #[test]
     fn test_query_token_summary() {
-        let deps = mock_dependencies();
+        let deps = fixture();
 
-        let msg = QueryMsg::GetTokenSummary {
-            token_id: "test_token".to_string(),
-        };
-        let res = query(deps.as_ref(), mock_env(), msg);
-        
-        // In a real test, this would succeed with mocked TSB data
-        assert!(res.is_err()); // Expected to fail in mock environment
+        let res: TokenSummary = run(
+            &deps,
+            QueryMsg::GetTokenSummary {
+                token_id: "token_1".to_string(),
+            },
+        );
+        assert_eq!(
+            res,
+            TokenSummary {
+                token: Some(mock_token("token_1", "torram1creator", "1000")),
+                total_supply: "1000".to_string(),
+                holder_count: 2,
+                operations_count: 2,
+                pending_sync: true,
+            }
+        );
+
+        let res: TokenSummary = run(
+            &deps,
+            QueryMsg::GetTokenSummary {
+                token_id: "token_2".to_string(),
+            },
+        );
+        assert_eq!(res.holder_count, 1);
+        assert_eq!(res.operations_count, 0);
+        assert!(!res.pending_sync);
     }
 
     #[test]
     fn test_query_user_portfolio() {
-        let deps = mock_dependencies();
+        let deps = fixture();
+
+        let res: UserPortfolio = run(
+            &deps,
+            QueryMsg::GetUserPortfolio {
+                owner: "torram1alice".to_string(),
+            },
+        );
+        assert_eq!(res.owner, "torram1alice");
+        assert_eq!(res.total_tokens, 2);
+        assert_eq!(res.balances[0].amount, "600");
+    }
+
+    #[test]
+    fn test_query_sync_status() {
+        let deps = fixture();
+
+        let res: SyncStatus = run(&deps, QueryMsg::GetSyncStatus {});
+        assert_eq!(
+            res,
+            SyncStatus {
+                total_tokens: 2,
+                synced_tokens: 1,
+                pending_operations: 1,
+                tokens_for_sync: vec!["token_1".to_string()],
+            }
+        );
+    }
+
+    #[test]
+    fn test_list_queries_paginate() {
+        let deps = fixture();
+        let balances_page = |start_after: Option<String>| -> GetAllBalancesResponse {
+            run(
+                &deps,
+                QueryMsg::GetAllBalances {
+                    start_after,
+                    limit: Some(3),
+                },
+            )
+        };
+
+        let first = balances_page(None);
+        assert_eq!(first.balances.len(), 3);
+        assert_eq!(first.next_key.as_deref(), Some("token_1/torram1carol"));
+        let second = balances_page(first.next_key);
+        assert_eq!(second.balances.len(), 1);
+        assert_eq!(second.balances[0].token_id, "token_2");
+        assert_eq!(second.next_key, None);
+
+        let res: GetUTXOsResponse = run(
+            &deps,
+            QueryMsg::GetUtxos {
+                address: Some("bc1alice".to_string()),
+                start_after: Some("tx_a:0".to_string()),
+                limit: None,
+            },
+        );
+        assert_eq!(res.utxos, [mock_utxo("tx_b", 1, "0.25")]);
+        assert_eq!(res.next_key, None);
+
+        // explicit limits are capped at MAX_LIMIT
+        let querier = (0..150).fold(MockTsbQuerier::default(), |querier, i| {
+            querier.with_token(mock_token(
+                &format!("token_{:03}", i),
+                "torram1creator",
+                "1",
+            ))
+        });
+        let res: GetAllTokensResponse = run(
+            &mock_tsb_dependencies(querier),
+            QueryMsg::GetAllTokens {
+                start_after: None,
+                limit: Some(1000),
+            },
+        );
+        assert_eq!(res.tokens.len(), MAX_LIMIT as usize);
+        assert_eq!(res.next_key.as_deref(), Some("token_099"));
+    }
+
+    #[test]
+    fn test_aggregated_queries_use_counters() {
+        let deps = fixture();
+
+        let res: GetTokenStatsResponse = run(
+            &deps,
+            QueryMsg::GetTokenStats {
+                token_id: "token_1".to_string(),
+            },
+        );
+        assert_eq!(res.operations_count, 2);
+        assert_eq!(res.pending_operations, 1);
+        let res: GetHolderCountResponse = run(
+            &deps,
+            QueryMsg::GetHolderCount {
+                token_id: "token_1".to_string(),
+            },
+        );
+        assert_eq!(res.holder_count, 2);
+        let res: GetSyncCountersResponse = run(&deps, QueryMsg::GetSyncCounters {});
+        assert_eq!(
+            res,
+            GetSyncCountersResponse {
+                total_tokens: 2,
+                synced_tokens: 1,
+                pending_operations: 1,
+            }
+        );
+    }
+
+    #[test]
+    fn test_token_summary_query_cost_at_10k_balances() {
+        // Costs come from the mock querier's synthetic model (QUERY_COST and
+        // RESPONSE_BYTE_COST), not from metered execution
+        let mut querier =
+            MockTsbQuerier::default().with_token(mock_token("token_a", "torram1creator", "40000"));
+        for i in 0..10_000 {
+            querier = querier.with_balance("token_a", &format!("torram1holder{:05}", i), "4");
+        }
+        let deps = mock_tsb_dependencies(querier);
 
-        let msg = QueryMsg::GetUserPortfolio {
-            owner: "torram1test123".to_string(),
+        let res: TokenSummary = run(
+            &deps,
+            QueryMsg::GetTokenSummary {
+                token_id: "token_a".to_string(),
+            },
+        );
+        assert_eq!(res.holder_count, 10_000);
+        let counters_cost = deps.querier.query_cost();
+
+        // the same count by scanning every balance, as the summary used to
+        deps.querier.reset_cost();
+        let mut holders = 0;
+        for_each_page(
+            deps.as_ref(),
+            |start_after, limit| TSBQuery::GetAllBalances { start_after, limit },
+            |page: &GetAllBalancesResponse| {
+                holders += page
+                    .balances
+                    .iter()
+                    .filter(|b| b.token_id == "token_a" && b.amount != "0")
+                    .count();
+            },
+        )
+        .unwrap();
+        assert_eq!(holders, 10_000);
+        let scan_cost = deps.querier.query_cost();
+
+        assert!(counters_cost < 5_000);
+        assert!(scan_cost > 500 * counters_cost);
+    }
+
+    #[test]
+    fn test_mock_rejects_other_queries() {
+        let deps = fixture();
+        let querier = QuerierWrapper::<Empty>::new(&deps.querier);
+
+        let err = querier
+            .query_balance("torram1alice", "utorram")
+            .unwrap_err();
+        assert!(err.to_string().contains("only answers TSB queries"));
+    }
+
+    #[test]
+    fn test_malformed_responses_are_rejected() {
+        let query_with = |querier: MockTsbQuerier, msg: QueryMsg| {
+            let deps = mock_tsb_dependencies(querier);
+            query(deps.as_ref(), mock_env(), msg).unwrap_err()
         };
-        let res = query(deps.as_ref(), mock_env(), msg);
-        
-        // In a real test, this would succeed with mocked TSB data
-        assert!(res.is_err()); // Expected to fail in mock environment
+
+        // a field renamed in the Go bindings
+        let querier = MockTsbQuerier::default().with_raw_response(
+            TSBQuery::GetAllTokens {
+                start_after: None,
+                limit: None,
+            },
+            br#"{"tokens":[{"id":"token_1"}]}"#.to_vec(),
+        );
+        let err = query_with(
+            querier,
+            QueryMsg::GetAllTokens {
+                start_after: None,
+                limit: None,
+            },
+        );
+        assert!(matches!(
+            &err,
+            ContractError::MalformedResponse { method: "get_all_tokens", reason }
+                if reason.contains("missing field")
+        ));
+
+        let querier =
+            MockTsbQuerier::default().with_token(mock_token("token_1", "torram1creator", "1e3"));
+        let err = query_with(
+            querier,
+            QueryMsg::GetTokenSummary {
+                token_id: "token_1".to_string(),
+            },
+        );
+        assert_eq!(
+            err.to_string(),
+            "Malformed get_token response from the TSB module: \
+             token amount \"1e3\" is not an integer amount"
+        );
+
+        let token = to_json_vec(&GetTokenResponse {
+            token: Some(mock_token("token_2", "torram1creator", "1")),
+        })
+        .unwrap();
+        let querier = MockTsbQuerier::default().with_raw_response(
+            TSBQuery::GetToken {
+                token_id: "token_1".to_string(),
+            },
+            token,
+        );
+        let err = query_with(
+            querier,
+            QueryMsg::GetToken {
+                token_id: "token_1".to_string(),
+            },
+        );
+        assert!(matches!(
+            &err,
+            ContractError::MalformedResponse { method: "get_token", reason }
+                if reason == "token_id \"token_2\" does not match the requested \"token_1\""
+        ));
+
+        let querier = MockTsbQuerier::default()
+            .with_balance("token_1", "torram1alice", "600")
+            .with_raw_response(
+                TSBQuery::GetBalancesByOwner {
+                    owner: "torram1bob".to_string(),
+                },
+                to_json_vec(&GetBalancesByOwnerResponse {
+                    balances: vec![TSBBalance {
+                        token_id: "token_1".to_string(),
+                        owner: "torram1alice".to_string(),
+                        amount: "600".to_string(),
+                    }],
+                })
+                .unwrap(),
+            );
+        let err = query_with(
+            querier,
+            QueryMsg::GetUserPortfolio {
+                owner: "torram1bob".to_string(),
+            },
+        );
+        assert!(err.to_string().contains("owner \"torram1alice\""));
+
+        // a cursor that does not advance would page forever
+        let stuck = to_json_vec(&GetAllBalancesResponse {
+            balances: vec![TSBBalance {
+                token_id: "token_1".to_string(),
+                owner: "torram1alice".to_string(),
+                amount: "600".to_string(),
+            }],
+            next_key: Some("token_1/torram1alice".to_string()),
+        })
+        .unwrap();
+        let querier = MockTsbQuerier::default().with_raw_response(
+            TSBQuery::GetAllBalances {
+                start_after: Some("token_1/torram1alice".to_string()),
+                limit: Some(1),
+            },
+            stuck,
+        );
+        let err = query_with(
+            querier,
+            QueryMsg::GetAllBalances {
+                start_after: Some("token_1/torram1alice".to_string()),
+                limit: Some(1),
+            },
+        );
+        assert!(err
+            .to_string()
+            .ends_with("next_key \"token_1/torram1alice\" repeats start_after"));
+
+        let querier = MockTsbQuerier::default().with_raw_response(
+            TSBQuery::GetUtxos {
+                address: None,
+                start_after: None,
+                limit: Some(1),
+            },
+            to_json_vec(&GetUTXOsResponse {
+                utxos: vec![mock_utxo("tx_a", 0, "1"), mock_utxo("tx_b", 0, "1")],
+                next_key: None,
+            })
+            .unwrap(),
+        );
+        let err = query_with(
+            querier,
+            QueryMsg::GetUtxos {
+                address: None,
+                start_after: None,
+                limit: Some(1),
+            },
+        );
+        assert!(err.to_string().ends_with("2 items exceed the limit of 1"));
+
+        let querier = MockTsbQuerier::default().with_raw_response(
+            TSBQuery::GetSyncCounters {},
+            to_json_vec(&GetSyncCountersResponse {
+                total_tokens: 1,
+                synced_tokens: 2,
+                pending_operations: 0,
+            })
+            .unwrap(),
+        );
+        let err = query_with(querier, QueryMsg::GetSyncStatus {});
+        assert!(matches!(
+            &err,
+            ContractError::MalformedResponse { method: "get_sync_counters", reason }
+                if reason == "synced_tokens 2 exceeds total_tokens 1"
+        ));
     }
 
     #[test]
     fn test_execute_fails() {
-        let mut deps = mock_dependencies();
+        let mut deps = fixture();
         let env = mock_env();
-        let info = mock_info("anyone", &[]);
+        let info = message_info(&deps.api.addr_make("anyone"), &[]);
 
         // This contract is read-only, so any execute should fail
         let res = execute(deps.as_mut(), env, info, ExecuteMsg {});
         assert!(res.is_err());
         assert!(res.unwrap_err().to_string().contains("read-only"));
     }
-} 
\ No newline at end of file
+}
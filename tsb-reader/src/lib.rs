@@ -1,155 +1,112 @@
 use cosmwasm_std::{
-    entry_point, to_binary, Binary, Deps, DepsMut, Env, MessageInfo, Response, StdResult,
-    CustomQuery, StdError, QueryRequest, Empty,
+    entry_point, from_json, to_json_binary, to_json_vec, Binary, ContractResult, Decimal, Deps,
+    DepsMut, Env, MessageInfo, QueryRequest, Response, StdError, StdResult, SystemResult, Uint128,
 };
 use schemars::JsonSchema;
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+use thiserror::Error;
+
+pub use torram_bindings::{
+    GetAllBalancesResponse, GetAllTokensResponse, GetBalancesByOwnerResponse,
+    GetHolderCountResponse, GetPendingBitcoinSyncResponse, GetSyncCountersResponse,
+    GetTokenBalanceResponse, GetTokenOperationResponse, GetTokenOperationsResponse,
+    GetTokenResponse, GetTokenStatsResponse, GetTokensByCreatorResponse, GetTokensForSyncResponse,
+    GetUTXOsResponse, TSBBalance, TSBOperation, TSBQuery, TSBToken, TSBCTXO,
+};
 
-// TSB Query types that match the Go bindings
-#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
-#[serde(rename_all = "snake_case")]
-pub enum TSBQuery {
-    GetAllTokens {},
-    GetToken { token_id: String },
-    GetTokensByCreator { creator: String },
-    GetAllBalances {},
-    GetTokenBalance { token_id: String, owner: String },
-    GetBalancesByOwner { owner: String },
-    GetTokenOperations { token_id: String },
-    GetTokenOperation { operation_id: String },
-    GetPendingBitcoinSync {},
-    GetTokensForSync {},
-    GetUtxos { address: Option<String> },
-}
-
-impl CustomQuery for TSBQuery {}
-
-// TSB Response types
-#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
-pub struct TSBToken {
-    pub token_id: String,
-    pub amount: String,
-    pub type_code: u32,
-    pub metadata: String,
-    pub creator: String,
-    pub creation_time: String,
-    pub bitcoin_tx_id: String,
-    pub synced_with_bitcoin: bool,
-}
-
-#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
-pub struct TSBBalance {
-    pub token_id: String,
-    pub owner: String,
-    pub amount: String,
-}
-
-#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
-pub struct TSBOperation {
-    pub operation_id: String,
-    pub token_id: String,
-    pub r#type: u32,
-    pub from: String,
-    pub to: String,
-    pub amount: String,
-    pub timestamp: String,
-    pub bitcoin_tx_id: String,
-    pub torram_tx_id: String,
-}
-
-#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
-pub struct TSBCTXO {
-    pub tx_id: String,
-    pub vout: u32,
-    pub amount: String,
-    pub used: bool,
-}
-
-// Response wrappers
-#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
-pub struct GetAllTokensResponse {
-    pub tokens: Vec<TSBToken>,
-}
-
-#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
-pub struct GetTokenResponse {
-    pub token: Option<TSBToken>,
-}
-
-#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
-pub struct GetTokensByCreatorResponse {
-    pub tokens: Vec<TSBToken>,
-}
-
-#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
-pub struct GetAllBalancesResponse {
-    pub balances: Vec<TSBBalance>,
-}
-
-#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
-pub struct GetTokenBalanceResponse {
-    pub balance: Option<TSBBalance>,
-}
-
-#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
-pub struct GetBalancesByOwnerResponse {
-    pub balances: Vec<TSBBalance>,
-}
+#[cfg(not(target_arch = "wasm32"))]
+pub mod testing;
 
-#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
-pub struct GetTokenOperationsResponse {
-    pub operations: Vec<TSBOperation>,
-}
+#[cfg(test)]
+mod integration_test;
 
-#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
-pub struct GetTokenOperationResponse {
-    pub operation: Option<TSBOperation>,
-}
+#[derive(Error, Debug)]
+pub enum ContractError {
+    #[error("{0}")]
+    Std(#[from] StdError),
 
-#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
-pub struct GetPendingBitcoinSyncResponse {
-    pub operations: Vec<TSBOperation>,
-}
+    #[error("This contract is read-only")]
+    ReadOnly {},
 
-#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
-pub struct GetTokensForSyncResponse {
-    pub token_ids: Vec<String>,
+    #[error("Malformed {method} response from the TSB module: {reason}")]
+    MalformedResponse {
+        method: &'static str,
+        reason: String,
+    },
 }
 
-#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
-pub struct GetUTXOsResponse {
-    pub utxos: Vec<TSBCTXO>,
-}
+// Largest page a list query may ask the TSB module for; aggregated queries
+// read in pages of this size
+pub const MAX_LIMIT: u32 = 100;
 
 // Contract messages
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct InstantiateMsg {}
 
+// This contract is read-only, every execute message is rejected
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
-#[serde(rename_all = "snake_case")]
-pub enum ExecuteMsg {
-    // This contract is read-only, no execute messages
-}
+pub struct ExecuteMsg {}
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum QueryMsg {
-    // TSB read functions exposed to CosmWasm
-    GetAllTokens {},
-    GetToken { token_id: String },
-    GetTokensByCreator { creator: String },
-    GetAllBalances {},
-    GetTokenBalance { token_id: String, owner: String },
-    GetBalancesByOwner { owner: String },
-    GetTokenOperations { token_id: String },
-    GetTokenOperation { operation_id: String },
+    // TSB read functions exposed to CosmWasm. The list queries take a
+    // `start_after` cursor (the `next_key` of the previous page) and a
+    // `limit` of at most `MAX_LIMIT`; without one the module's default
+    // page is returned.
+    GetAllTokens {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    GetToken {
+        token_id: String,
+    },
+    GetTokensByCreator {
+        creator: String,
+    },
+    GetAllBalances {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    GetTokenBalance {
+        token_id: String,
+        owner: String,
+    },
+    GetBalancesByOwner {
+        owner: String,
+    },
+    GetTokenOperations {
+        token_id: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    GetTokenOperation {
+        operation_id: String,
+    },
     GetPendingBitcoinSync {},
     GetTokensForSync {},
-    GetUtxos { address: Option<String> },
-    
+    GetUtxos {
+        address: Option<String>,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    GetTokenStats {
+        token_id: String,
+    },
+    GetHolderCount {
+        token_id: String,
+    },
+    GetSyncCounters {},
+
     // Aggregated queries for convenience
-    GetTokenSummary { token_id: String },
-    GetUserPortfolio { owner: String },
+    GetTokenSummary {
+        token_id: String,
+    },
+    GetUserPortfolio {
+        owner: String,
+    },
     GetSyncStatus {},
 }
 
@@ -181,7 +138,7 @@ pub struct SyncStatus {
 
 #[entry_point]
 pub fn instantiate(
-    _deps: DepsMut,
+    _deps: DepsMut<TSBQuery>,
     _env: Env,
     _info: MessageInfo,
     _msg: InstantiateMsg,
@@ -191,31 +148,87 @@ pub fn instantiate(
 
 #[entry_point]
 pub fn execute(
-    _deps: DepsMut,
+    _deps: DepsMut<TSBQuery>,
     _env: Env,
     _info: MessageInfo,
     _msg: ExecuteMsg,
-) -> StdResult<Response> {
-    // This contract is read-only
-    Err(StdError::generic_err("This contract is read-only"))
+) -> Result<Response, ContractError> {
+    Err(ContractError::ReadOnly {})
 }
 
 #[entry_point]
-pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
+pub fn query(deps: Deps<TSBQuery>, _env: Env, msg: QueryMsg) -> Result<Binary, ContractError> {
     match msg {
         // Direct TSB queries
-        QueryMsg::GetAllTokens {} => query_all_tokens(deps),
-        QueryMsg::GetToken { token_id } => query_token(deps, token_id),
-        QueryMsg::GetTokensByCreator { creator } => query_tokens_by_creator(deps, creator),
-        QueryMsg::GetAllBalances {} => query_all_balances(deps),
-        QueryMsg::GetTokenBalance { token_id, owner } => query_token_balance(deps, token_id, owner),
-        QueryMsg::GetBalancesByOwner { owner } => query_balances_by_owner(deps, owner),
-        QueryMsg::GetTokenOperations { token_id } => query_token_operations(deps, token_id),
-        QueryMsg::GetTokenOperation { operation_id } => query_token_operation(deps, operation_id),
-        QueryMsg::GetPendingBitcoinSync {} => query_pending_bitcoin_sync(deps),
-        QueryMsg::GetTokensForSync {} => query_tokens_for_sync(deps),
-        QueryMsg::GetUtxos { address } => query_utxos(deps, address),
-        
+        QueryMsg::GetAllTokens { start_after, limit } => forward::<GetAllTokensResponse>(
+            deps,
+            TSBQuery::GetAllTokens {
+                start_after,
+                limit: cap(limit),
+            },
+        ),
+        QueryMsg::GetToken { token_id } => {
+            forward::<GetTokenResponse>(deps, TSBQuery::GetToken { token_id })
+        }
+        QueryMsg::GetTokensByCreator { creator } => {
+            forward::<GetTokensByCreatorResponse>(deps, TSBQuery::GetTokensByCreator { creator })
+        }
+        QueryMsg::GetAllBalances { start_after, limit } => forward::<GetAllBalancesResponse>(
+            deps,
+            TSBQuery::GetAllBalances {
+                start_after,
+                limit: cap(limit),
+            },
+        ),
+        QueryMsg::GetTokenBalance { token_id, owner } => {
+            forward::<GetTokenBalanceResponse>(deps, TSBQuery::GetTokenBalance { token_id, owner })
+        }
+        QueryMsg::GetBalancesByOwner { owner } => {
+            forward::<GetBalancesByOwnerResponse>(deps, TSBQuery::GetBalancesByOwner { owner })
+        }
+        QueryMsg::GetTokenOperations {
+            token_id,
+            start_after,
+            limit,
+        } => forward::<GetTokenOperationsResponse>(
+            deps,
+            TSBQuery::GetTokenOperations {
+                token_id,
+                start_after,
+                limit: cap(limit),
+            },
+        ),
+        QueryMsg::GetTokenOperation { operation_id } => {
+            forward::<GetTokenOperationResponse>(deps, TSBQuery::GetTokenOperation { operation_id })
+        }
+        QueryMsg::GetPendingBitcoinSync {} => {
+            forward::<GetPendingBitcoinSyncResponse>(deps, TSBQuery::GetPendingBitcoinSync {})
+        }
+        QueryMsg::GetTokensForSync {} => {
+            forward::<GetTokensForSyncResponse>(deps, TSBQuery::GetTokensForSync {})
+        }
+        QueryMsg::GetTokenStats { token_id } => {
+            forward::<GetTokenStatsResponse>(deps, TSBQuery::GetTokenStats { token_id })
+        }
+        QueryMsg::GetHolderCount { token_id } => {
+            forward::<GetHolderCountResponse>(deps, TSBQuery::GetHolderCount { token_id })
+        }
+        QueryMsg::GetSyncCounters {} => {
+            forward::<GetSyncCountersResponse>(deps, TSBQuery::GetSyncCounters {})
+        }
+        QueryMsg::GetUtxos {
+            address,
+            start_after,
+            limit,
+        } => forward::<GetUTXOsResponse>(
+            deps,
+            TSBQuery::GetUtxos {
+                address,
+                start_after,
+                limit: cap(limit),
+            },
+        ),
+
         // Aggregated queries
         QueryMsg::GetTokenSummary { token_id } => query_token_summary(deps, token_id),
         QueryMsg::GetUserPortfolio { owner } => query_user_portfolio(deps, owner),
@@ -223,123 +236,337 @@ pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
     }
 }
 
-// Helper function to make TSB queries
-fn make_tsb_query<T: CustomQuery>(deps: Deps, query: T) -> StdResult<Binary> {
-    let request: QueryRequest<Empty> = QueryRequest::Custom(query);
-    deps.querier.query(&request)
+/// A TSB module response that can be checked against the query it answers.
+pub trait TsbResponse: Serialize + DeserializeOwned {
+    /// Describes the first problem found, if any.
+    fn validate(&self, query: &TSBQuery) -> Result<(), String>;
 }
 
-// Direct TSB query implementations
-fn query_all_tokens(deps: Deps) -> StdResult<Binary> {
-    make_tsb_query(deps, TSBQuery::GetAllTokens {})
+fn cap(limit: Option<u32>) -> Option<u32> {
+    limit.map(|limit| limit.min(MAX_LIMIT))
 }
 
-fn query_token(deps: Deps, token_id: String) -> StdResult<Binary> {
-    make_tsb_query(deps, TSBQuery::GetToken { token_id })
+fn ensure_non_empty(field: &str, value: &str) -> Result<(), String> {
+    if value.is_empty() {
+        return Err(format!("{} is empty", field));
+    }
+    Ok(())
 }
 
-fn query_tokens_by_creator(deps: Deps, creator: String) -> StdResult<Binary> {
-    make_tsb_query(deps, TSBQuery::GetTokensByCreator { creator })
+fn ensure_integer(field: &str, value: &str) -> Result<(), String> {
+    Uint128::from_str(value)
+        .map(|_| ())
+        .map_err(|_| format!("{} {:?} is not an integer amount", field, value))
 }
 
-fn query_all_balances(deps: Deps) -> StdResult<Binary> {
-    make_tsb_query(deps, TSBQuery::GetAllBalances {})
+fn ensure_matches(field: &str, got: &str, requested: &str) -> Result<(), String> {
+    if got != requested {
+        return Err(format!(
+            "{} {:?} does not match the requested {:?}",
+            field, got, requested
+        ));
+    }
+    Ok(())
 }
 
-fn query_token_balance(deps: Deps, token_id: String, owner: String) -> StdResult<Binary> {
-    make_tsb_query(deps, TSBQuery::GetTokenBalance { token_id, owner })
+fn ensure_at_most(field: &str, value: u32, bound_field: &str, bound: u32) -> Result<(), String> {
+    if value > bound {
+        return Err(format!(
+            "{} {} exceeds {} {}",
+            field, value, bound_field, bound
+        ));
+    }
+    Ok(())
 }
 
-fn query_balances_by_owner(deps: Deps, owner: String) -> StdResult<Binary> {
-    make_tsb_query(deps, TSBQuery::GetBalancesByOwner { owner })
+// A page must respect the requested limit and its cursor must move forward,
+// so that walking the pages always ends
+fn ensure_page(len: usize, next_key: Option<&str>, query: &TSBQuery) -> Result<(), String> {
+    let (start_after, limit) = query.page().unwrap_or_default();
+    if let Some(limit) = limit {
+        if len > limit as usize {
+            return Err(format!("{} items exceed the limit of {}", len, limit));
+        }
+    }
+    if let Some(next_key) = next_key {
+        if len == 0 {
+            return Err("next_key is set on an empty page".to_string());
+        }
+        ensure_non_empty("next_key", next_key)?;
+        if Some(next_key) == start_after {
+            return Err(format!("next_key {:?} repeats start_after", next_key));
+        }
+    }
+    Ok(())
+}
+
+fn validate_token(token: &TSBToken) -> Result<(), String> {
+    ensure_non_empty("token_id", &token.token_id)?;
+    ensure_integer("token amount", &token.amount)
+}
+
+fn validate_balance(balance: &TSBBalance) -> Result<(), String> {
+    ensure_non_empty("token_id", &balance.token_id)?;
+    ensure_non_empty("owner", &balance.owner)?;
+    ensure_integer("balance amount", &balance.amount)
+}
+
+fn validate_operation(operation: &TSBOperation) -> Result<(), String> {
+    ensure_non_empty("operation_id", &operation.operation_id)?;
+    ensure_non_empty("token_id", &operation.token_id)?;
+    ensure_integer("operation amount", &operation.amount)
+}
+
+fn validate_utxo(utxo: &TSBCTXO) -> Result<(), String> {
+    ensure_non_empty("tx_id", &utxo.tx_id)?;
+    Decimal::from_str(&utxo.amount)
+        .map(|_| ())
+        .map_err(|_| format!("UTXO amount {:?} is not a decimal", utxo.amount))
+}
+
+impl TsbResponse for GetAllTokensResponse {
+    fn validate(&self, query: &TSBQuery) -> Result<(), String> {
+        ensure_page(self.tokens.len(), self.next_key.as_deref(), query)?;
+        self.tokens.iter().try_for_each(validate_token)
+    }
+}
+
+impl TsbResponse for GetTokenResponse {
+    fn validate(&self, query: &TSBQuery) -> Result<(), String> {
+        if let (Some(token), TSBQuery::GetToken { token_id }) = (&self.token, query) {
+            validate_token(token)?;
+            ensure_matches("token_id", &token.token_id, token_id)?;
+        }
+        Ok(())
+    }
+}
+
+impl TsbResponse for GetTokensByCreatorResponse {
+    fn validate(&self, query: &TSBQuery) -> Result<(), String> {
+        self.tokens.iter().try_for_each(|token| {
+            validate_token(token)?;
+            match query {
+                TSBQuery::GetTokensByCreator { creator } => {
+                    ensure_matches("creator", &token.creator, creator)
+                }
+                _ => Ok(()),
+            }
+        })
+    }
+}
+
+impl TsbResponse for GetAllBalancesResponse {
+    fn validate(&self, query: &TSBQuery) -> Result<(), String> {
+        ensure_page(self.balances.len(), self.next_key.as_deref(), query)?;
+        self.balances.iter().try_for_each(validate_balance)
+    }
+}
+
+impl TsbResponse for GetTokenBalanceResponse {
+    fn validate(&self, query: &TSBQuery) -> Result<(), String> {
+        if let (Some(balance), TSBQuery::GetTokenBalance { token_id, owner }) =
+            (&self.balance, query)
+        {
+            validate_balance(balance)?;
+            ensure_matches("token_id", &balance.token_id, token_id)?;
+            ensure_matches("owner", &balance.owner, owner)?;
+        }
+        Ok(())
+    }
+}
+
+impl TsbResponse for GetBalancesByOwnerResponse {
+    fn validate(&self, query: &TSBQuery) -> Result<(), String> {
+        self.balances.iter().try_for_each(|balance| {
+            validate_balance(balance)?;
+            match query {
+                TSBQuery::GetBalancesByOwner { owner } => {
+                    ensure_matches("owner", &balance.owner, owner)
+                }
+                _ => Ok(()),
+            }
+        })
+    }
 }
 
-fn query_token_operations(deps: Deps, token_id: String) -> StdResult<Binary> {
-    make_tsb_query(deps, TSBQuery::GetTokenOperations { token_id })
+impl TsbResponse for GetTokenOperationsResponse {
+    fn validate(&self, query: &TSBQuery) -> Result<(), String> {
+        ensure_page(self.operations.len(), self.next_key.as_deref(), query)?;
+        self.operations.iter().try_for_each(|operation| {
+            validate_operation(operation)?;
+            match query {
+                TSBQuery::GetTokenOperations { token_id, .. } => {
+                    ensure_matches("token_id", &operation.token_id, token_id)
+                }
+                _ => Ok(()),
+            }
+        })
+    }
+}
+
+impl TsbResponse for GetTokenOperationResponse {
+    fn validate(&self, query: &TSBQuery) -> Result<(), String> {
+        if let (Some(operation), TSBQuery::GetTokenOperation { operation_id }) =
+            (&self.operation, query)
+        {
+            validate_operation(operation)?;
+            ensure_matches("operation_id", &operation.operation_id, operation_id)?;
+        }
+        Ok(())
+    }
+}
+
+impl TsbResponse for GetPendingBitcoinSyncResponse {
+    fn validate(&self, _query: &TSBQuery) -> Result<(), String> {
+        self.operations.iter().try_for_each(validate_operation)
+    }
+}
+
+impl TsbResponse for GetTokensForSyncResponse {
+    fn validate(&self, _query: &TSBQuery) -> Result<(), String> {
+        self.token_ids
+            .iter()
+            .try_for_each(|token_id| ensure_non_empty("token_id", token_id))
+    }
+}
+
+impl TsbResponse for GetUTXOsResponse {
+    fn validate(&self, query: &TSBQuery) -> Result<(), String> {
+        ensure_page(self.utxos.len(), self.next_key.as_deref(), query)?;
+        self.utxos.iter().try_for_each(validate_utxo)
+    }
+}
+
+impl TsbResponse for GetTokenStatsResponse {
+    fn validate(&self, query: &TSBQuery) -> Result<(), String> {
+        if let TSBQuery::GetTokenStats { token_id } = query {
+            ensure_matches("token_id", &self.token_id, token_id)?;
+        }
+        ensure_at_most(
+            "pending_operations",
+            self.pending_operations,
+            "operations_count",
+            self.operations_count,
+        )
+    }
 }
 
-fn query_token_operation(deps: Deps, operation_id: String) -> StdResult<Binary> {
-    make_tsb_query(deps, TSBQuery::GetTokenOperation { operation_id })
+impl TsbResponse for GetHolderCountResponse {
+    fn validate(&self, query: &TSBQuery) -> Result<(), String> {
+        match query {
+            TSBQuery::GetHolderCount { token_id } => {
+                ensure_matches("token_id", &self.token_id, token_id)
+            }
+            _ => Ok(()),
+        }
+    }
 }
 
-fn query_pending_bitcoin_sync(deps: Deps) -> StdResult<Binary> {
-    make_tsb_query(deps, TSBQuery::GetPendingBitcoinSync {})
+impl TsbResponse for GetSyncCountersResponse {
+    fn validate(&self, _query: &TSBQuery) -> Result<(), String> {
+        ensure_at_most(
+            "synced_tokens",
+            self.synced_tokens,
+            "total_tokens",
+            self.total_tokens,
+        )
+    }
 }
 
-fn query_tokens_for_sync(deps: Deps) -> StdResult<Binary> {
-    make_tsb_query(deps, TSBQuery::GetTokensForSync {})
+/// Runs `query` against the TSB module and decodes and validates its
+/// response, so that drift in the chain's bindings fails here rather than in
+/// the caller.
+pub fn query_tsb<T: TsbResponse>(
+    deps: Deps<TSBQuery>,
+    query: TSBQuery,
+) -> Result<T, ContractError> {
+    let method = query.method();
+    let request = to_json_vec(&QueryRequest::Custom(query.clone()))?;
+    let raw = match deps.querier.raw_query(&request) {
+        SystemResult::Err(err) => {
+            return Err(StdError::generic_err(format!("Querier system error: {}", err)).into())
+        }
+        SystemResult::Ok(ContractResult::Err(err)) => {
+            return Err(StdError::generic_err(format!("Querier contract error: {}", err)).into())
+        }
+        SystemResult::Ok(ContractResult::Ok(value)) => value,
+    };
+    let response: T = from_json(&raw).map_err(|err| ContractError::MalformedResponse {
+        method,
+        reason: err.to_string(),
+    })?;
+    response
+        .validate(&query)
+        .map_err(|reason| ContractError::MalformedResponse { method, reason })?;
+    Ok(response)
 }
 
-fn query_utxos(deps: Deps, address: Option<String>) -> StdResult<Binary> {
-    make_tsb_query(deps, TSBQuery::GetUtxos { address })
+// Direct TSB queries return the validated response re-encoded
+fn forward<T: TsbResponse>(deps: Deps<TSBQuery>, query: TSBQuery) -> Result<Binary, ContractError> {
+    Ok(to_json_binary(&query_tsb::<T>(deps, query)?)?)
 }
 
 // Aggregated query implementations
-fn query_token_summary(deps: Deps, token_id: String) -> StdResult<Binary> {
-    // Get token info
-    let token_response: GetTokenResponse = deps.querier.query(&QueryRequest::Custom(TSBQuery::GetToken { token_id: token_id.clone() }))?;
-    
-    // Get operations count
-    let operations_response: GetTokenOperationsResponse = deps.querier.query(&QueryRequest::Custom(TSBQuery::GetTokenOperations { token_id: token_id.clone() }))?;
-    
-    // Get all balances to calculate holder count
-    let balances_response: GetAllBalancesResponse = deps.querier.query(&QueryRequest::Custom(TSBQuery::GetAllBalances {}))?;
-    let holder_count = balances_response.balances.iter()
-        .filter(|b| b.token_id == token_id && b.amount != "0")
-        .count() as u32;
-    
-    // Check if pending sync
-    let pending_response: GetPendingBitcoinSyncResponse = deps.querier.query(&QueryRequest::Custom(TSBQuery::GetPendingBitcoinSync {}))?;
-    let pending_sync = pending_response.operations.iter()
-        .any(|op| op.token_id == token_id);
-    
-    let total_supply = token_response.token.as_ref().map(|t| t.amount.clone()).unwrap_or_default();
-    let token_clone = token_response.token.clone();
-    
+// The module keeps per-token counters, so a summary costs three lookups
+// however many balances and operations the chain holds
+fn query_token_summary(deps: Deps<TSBQuery>, token_id: String) -> Result<Binary, ContractError> {
+    let token_response: GetTokenResponse = query_tsb(
+        deps,
+        TSBQuery::GetToken {
+            token_id: token_id.clone(),
+        },
+    )?;
+    let stats: GetTokenStatsResponse = query_tsb(
+        deps,
+        TSBQuery::GetTokenStats {
+            token_id: token_id.clone(),
+        },
+    )?;
+    let holders: GetHolderCountResponse = query_tsb(deps, TSBQuery::GetHolderCount { token_id })?;
+
     let summary = TokenSummary {
-        token: token_clone,
-        total_supply,
-        holder_count,
-        operations_count: operations_response.operations.len() as u32,
-        pending_sync,
+        total_supply: token_response
+            .token
+            .as_ref()
+            .map(|t| t.amount.clone())
+            .unwrap_or_default(),
+        token: token_response.token,
+        holder_count: holders.holder_count,
+        operations_count: stats.operations_count,
+        pending_sync: stats.pending_operations > 0,
     };
-    
-    to_binary(&summary)
+
+    Ok(to_json_binary(&summary)?)
 }
 
-fn query_user_portfolio(deps: Deps, owner: String) -> StdResult<Binary> {
-    let balances_response: GetBalancesByOwnerResponse = deps.querier.query(&QueryRequest::Custom(TSBQuery::GetBalancesByOwner { owner: owner.clone() }))?;
-    
+fn query_user_portfolio(deps: Deps<TSBQuery>, owner: String) -> Result<Binary, ContractError> {
+    let balances_response: GetBalancesByOwnerResponse = query_tsb(
+        deps,
+        TSBQuery::GetBalancesByOwner {
+            owner: owner.clone(),
+        },
+    )?;
+
     let portfolio = UserPortfolio {
         owner,
         total_tokens: balances_response.balances.len() as u32,
         balances: balances_response.balances,
         total_value: "0".to_string(), // Would need price oracle integration
     };
-    
-    to_binary(&portfolio)
-}
-
-fn query_sync_status(deps: Deps) -> StdResult<Binary> {
-    // Get all tokens
-    let tokens_response: GetAllTokensResponse = deps.querier.query(&QueryRequest::Custom(TSBQuery::GetAllTokens {}))?;
-    
-    // Get pending operations
-    let pending_response: GetPendingBitcoinSyncResponse = deps.querier.query(&QueryRequest::Custom(TSBQuery::GetPendingBitcoinSync {}))?;
-    
-    // Get tokens for sync
-    let sync_response: GetTokensForSyncResponse = deps.querier.query(&QueryRequest::Custom(TSBQuery::GetTokensForSync {}))?;
-    
-    let synced_count = tokens_response.tokens.iter()
-        .filter(|t| t.synced_with_bitcoin)
-        .count() as u32;
-    
+
+    Ok(to_json_binary(&portfolio)?)
+}
+
+fn query_sync_status(deps: Deps<TSBQuery>) -> Result<Binary, ContractError> {
+    let counters: GetSyncCountersResponse = query_tsb(deps, TSBQuery::GetSyncCounters {})?;
+    let sync_response: GetTokensForSyncResponse = query_tsb(deps, TSBQuery::GetTokensForSync {})?;
+
     let status = SyncStatus {
-        total_tokens: tokens_response.tokens.len() as u32,
-        synced_tokens: synced_count,
-        pending_operations: pending_response.operations.len() as u32,
+        total_tokens: counters.total_tokens,
+        synced_tokens: counters.synced_tokens,
+        pending_operations: counters.pending_operations,
         tokens_for_sync: sync_response.token_ids,
     };
-    
-    to_binary(&status)
-} 
\ No newline at end of file
+
+    Ok(to_json_binary(&status)?)
+}
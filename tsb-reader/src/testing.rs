@@ -0,0 +1,400 @@
+//! In-memory stand-in for the TSB module, so contracts issuing `TSBQuery`
+//! can be unit tested.
+//!
+//! ```ignore
+//! let querier = MockTsbQuerier::default()
+//!     .with_token(mock_token("token_1", "torram1creator", "1000"))
+//!     .with_balance("token_1", "torram1alice", "600");
+//! let deps = mock_tsb_dependencies(querier);
+//! ```
+
+use crate::*;
+use cosmwasm_std::testing::{MockApi, MockStorage};
+use cosmwasm_std::{from_json, OwnedDeps, Querier, QuerierResult, SystemError};
+use std::cell::Cell;
+use std::collections::BTreeMap;
+use std::marker::PhantomData;
+
+pub type MockTsbDeps = OwnedDeps<MockStorage, MockApi, MockTsbQuerier, TSBQuery>;
+
+/// Dependencies whose querier answers `TSBQuery` from `querier`.
+pub fn mock_tsb_dependencies(querier: MockTsbQuerier) -> MockTsbDeps {
+    OwnedDeps {
+        storage: MockStorage::default(),
+        api: MockApi::default(),
+        querier,
+        custom_query_type: PhantomData,
+    }
+}
+
+/// Cost `MockTsbQuerier::query_cost` charges per query, modelled on the
+/// SDK's flat gas cost of a store read.
+pub const QUERY_COST: u64 = 1_000;
+/// Cost `MockTsbQuerier::query_cost` charges per response byte, modelled on
+/// the SDK's per-byte gas cost of a store read.
+pub const RESPONSE_BYTE_COST: u64 = 3;
+
+/// TSB module state served to `TSBQuery` requests. Any other request fails
+/// with a system error.
+#[derive(Clone, Debug, Default)]
+pub struct MockTsbQuerier {
+    pub tokens: Vec<TSBToken>,
+    pub balances: Vec<TSBBalance>,
+    pub operations: Vec<TSBOperation>,
+    /// Ids of the `operations` still waiting to be synced to Bitcoin.
+    pub pending_sync: Vec<String>,
+    /// Token ids returned by `GetTokensForSync`.
+    pub tokens_for_sync: Vec<String>,
+    /// UTXOs by Bitcoin address.
+    pub utxos: BTreeMap<String, Vec<TSBCTXO>>,
+    /// Responses returned verbatim for matching queries, ahead of the state above.
+    pub raw_responses: Vec<(TSBQuery, Binary)>,
+    query_cost: Cell<u64>,
+}
+
+impl MockTsbQuerier {
+    pub fn with_token(mut self, token: TSBToken) -> Self {
+        self.tokens.push(token);
+        self
+    }
+
+    pub fn with_balance(mut self, token_id: &str, owner: &str, amount: &str) -> Self {
+        self.balances.push(TSBBalance {
+            token_id: token_id.to_string(),
+            owner: owner.to_string(),
+            amount: amount.to_string(),
+        });
+        self
+    }
+
+    pub fn with_operation(mut self, operation: TSBOperation) -> Self {
+        self.operations.push(operation);
+        self
+    }
+
+    /// Adds `operation` as waiting for its Bitcoin sync.
+    pub fn with_pending_operation(mut self, operation: TSBOperation) -> Self {
+        self.pending_sync.push(operation.operation_id.clone());
+        self.with_operation(operation)
+    }
+
+    pub fn with_token_for_sync(mut self, token_id: &str) -> Self {
+        self.tokens_for_sync.push(token_id.to_string());
+        self
+    }
+
+    pub fn with_utxo(mut self, address: &str, utxo: TSBCTXO) -> Self {
+        self.utxos
+            .entry(address.to_string())
+            .or_default()
+            .push(utxo);
+        self
+    }
+
+    /// Answers `query` with `response` as is, e.g. to simulate a module whose
+    /// responses no longer match the contract's types.
+    pub fn with_raw_response(mut self, query: TSBQuery, response: impl Into<Binary>) -> Self {
+        self.raw_responses.push((query, response.into()));
+        self
+    }
+
+    /// Synthetic cost of the TSB queries answered so far: `QUERY_COST` per
+    /// query plus `RESPONSE_BYTE_COST` per response byte. It compares how
+    /// much data contracts read; it is not metered gas.
+    pub fn query_cost(&self) -> u64 {
+        self.query_cost.get()
+    }
+
+    pub fn reset_cost(&self) {
+        self.query_cost.set(0);
+    }
+
+    fn is_pending(&self, operation: &TSBOperation) -> bool {
+        self.pending_sync.contains(&operation.operation_id)
+    }
+
+    /// Answers `query` the way the TSB module would.
+    pub fn handle(&self, query: &TSBQuery) -> QuerierResult {
+        if let Some((_, raw)) = self.raw_responses.iter().find(|(q, _)| q == query) {
+            return SystemResult::Ok(ContractResult::Ok(raw.clone()));
+        }
+        let response = match query {
+            TSBQuery::GetAllTokens { start_after, limit } => {
+                let (tokens, next_key) = paginate(&self.tokens, token_key, start_after, *limit);
+                to_json_binary(&GetAllTokensResponse { tokens, next_key })
+            }
+            TSBQuery::GetToken { token_id } => to_json_binary(&GetTokenResponse {
+                token: self
+                    .tokens
+                    .iter()
+                    .find(|t| t.token_id == *token_id)
+                    .cloned(),
+            }),
+            TSBQuery::GetTokensByCreator { creator } => {
+                to_json_binary(&GetTokensByCreatorResponse {
+                    tokens: self
+                        .tokens
+                        .iter()
+                        .filter(|t| t.creator == *creator)
+                        .cloned()
+                        .collect(),
+                })
+            }
+            TSBQuery::GetAllBalances { start_after, limit } => {
+                let (balances, next_key) =
+                    paginate(&self.balances, balance_key, start_after, *limit);
+                to_json_binary(&GetAllBalancesResponse { balances, next_key })
+            }
+            TSBQuery::GetTokenBalance { token_id, owner } => {
+                to_json_binary(&GetTokenBalanceResponse {
+                    balance: self
+                        .balances
+                        .iter()
+                        .find(|b| b.token_id == *token_id && b.owner == *owner)
+                        .cloned(),
+                })
+            }
+            TSBQuery::GetBalancesByOwner { owner } => to_json_binary(&GetBalancesByOwnerResponse {
+                balances: self
+                    .balances
+                    .iter()
+                    .filter(|b| b.owner == *owner)
+                    .cloned()
+                    .collect(),
+            }),
+            TSBQuery::GetTokenOperations {
+                token_id,
+                start_after,
+                limit,
+            } => {
+                let operations = self.operations.iter().filter(|op| op.token_id == *token_id);
+                let (operations, next_key) =
+                    paginate(operations, operation_key, start_after, *limit);
+                to_json_binary(&GetTokenOperationsResponse {
+                    operations,
+                    next_key,
+                })
+            }
+            TSBQuery::GetTokenOperation { operation_id } => {
+                to_json_binary(&GetTokenOperationResponse {
+                    operation: self
+                        .operations
+                        .iter()
+                        .find(|op| op.operation_id == *operation_id)
+                        .cloned(),
+                })
+            }
+            TSBQuery::GetPendingBitcoinSync {} => to_json_binary(&GetPendingBitcoinSyncResponse {
+                operations: self
+                    .operations
+                    .iter()
+                    .filter(|op| self.is_pending(op))
+                    .cloned()
+                    .collect(),
+            }),
+            TSBQuery::GetTokensForSync {} => to_json_binary(&GetTokensForSyncResponse {
+                token_ids: self.tokens_for_sync.clone(),
+            }),
+            TSBQuery::GetUtxos {
+                address,
+                start_after,
+                limit,
+            } => {
+                let utxos: Vec<&TSBCTXO> = match address {
+                    Some(address) => self.utxos.get(address).into_iter().flatten().collect(),
+                    None => self.utxos.values().flatten().collect(),
+                };
+                let (utxos, next_key) = paginate(utxos, utxo_key, start_after, *limit);
+                to_json_binary(&GetUTXOsResponse { utxos, next_key })
+            }
+            TSBQuery::GetTokenStats { token_id } => {
+                let operations = self.operations.iter().filter(|op| op.token_id == *token_id);
+                to_json_binary(&GetTokenStatsResponse {
+                    token_id: token_id.clone(),
+                    operations_count: operations.clone().count() as u32,
+                    pending_operations: operations.filter(|op| self.is_pending(op)).count() as u32,
+                })
+            }
+            TSBQuery::GetHolderCount { token_id } => to_json_binary(&GetHolderCountResponse {
+                token_id: token_id.clone(),
+                holder_count: self
+                    .balances
+                    .iter()
+                    .filter(|b| b.token_id == *token_id && b.amount != "0")
+                    .count() as u32,
+            }),
+            TSBQuery::GetSyncCounters {} => to_json_binary(&GetSyncCountersResponse {
+                total_tokens: self.tokens.len() as u32,
+                synced_tokens: self.tokens.iter().filter(|t| t.synced_with_bitcoin).count() as u32,
+                pending_operations: self
+                    .operations
+                    .iter()
+                    .filter(|op| self.is_pending(op))
+                    .count() as u32,
+            }),
+        };
+        SystemResult::Ok(ContractResult::from(response))
+    }
+}
+
+// Pagination keys used by the mock: list queries return items in key order
+fn token_key(token: &TSBToken) -> String {
+    token.token_id.clone()
+}
+
+fn balance_key(balance: &TSBBalance) -> String {
+    format!("{}/{}", balance.token_id, balance.owner)
+}
+
+fn operation_key(operation: &TSBOperation) -> String {
+    operation.operation_id.clone()
+}
+
+fn utxo_key(utxo: &TSBCTXO) -> String {
+    format!("{}:{}", utxo.tx_id, utxo.vout)
+}
+
+/// The items after `start_after` in key order, at most `limit` of them
+/// (all without a limit), and the key to continue from if any are left.
+fn paginate<'a, T: Clone + 'a>(
+    items: impl IntoIterator<Item = &'a T>,
+    key: fn(&T) -> String,
+    start_after: &Option<String>,
+    limit: Option<u32>,
+) -> (Vec<T>, Option<String>) {
+    let mut keyed: Vec<(String, &T)> = items
+        .into_iter()
+        .map(|item| (key(item), item))
+        .filter(|(k, _)| start_after.as_ref().is_none_or(|start| k > start))
+        .collect();
+    keyed.sort_by(|a, b| a.0.cmp(&b.0));
+    let limit = limit.map_or(keyed.len(), |limit| limit as usize);
+    let next_key = if limit > 0 && keyed.len() > limit {
+        Some(keyed[limit - 1].0.clone())
+    } else {
+        None
+    };
+    keyed.truncate(limit);
+    let page = keyed.into_iter().map(|(_, item)| item.clone()).collect();
+    (page, next_key)
+}
+
+impl Querier for MockTsbQuerier {
+    fn raw_query(&self, bin_request: &[u8]) -> QuerierResult {
+        let request: QueryRequest<TSBQuery> = match from_json(bin_request) {
+            Ok(request) => request,
+            Err(err) => {
+                return SystemResult::Err(SystemError::InvalidRequest {
+                    error: format!("Parsing query request: {}", err),
+                    request: bin_request.into(),
+                })
+            }
+        };
+        match request {
+            QueryRequest::Custom(query) => {
+                let result = self.handle(&query);
+                if let SystemResult::Ok(ContractResult::Ok(response)) = &result {
+                    let cost = QUERY_COST + RESPONSE_BYTE_COST * response.len() as u64;
+                    self.query_cost.set(self.query_cost.get() + cost);
+                }
+                result
+            }
+            _ => SystemResult::Err(SystemError::UnsupportedRequest {
+                kind: "MockTsbQuerier only answers TSB queries".to_string(),
+            }),
+        }
+    }
+}
+
+/// A token fixture, not yet synced with Bitcoin.
+pub fn mock_token(token_id: &str, creator: &str, amount: &str) -> TSBToken {
+    TSBToken {
+        token_id: token_id.to_string(),
+        amount: amount.to_string(),
+        type_code: 1,
+        metadata: String::new(),
+        creator: creator.to_string(),
+        creation_time: "1700000000".to_string(),
+        bitcoin_tx_id: String::new(),
+        synced_with_bitcoin: false,
+    }
+}
+
+/// A transfer fixture of `amount` tokens from `from` to `to`.
+pub fn mock_operation(
+    operation_id: &str,
+    token_id: &str,
+    from: &str,
+    to: &str,
+    amount: &str,
+) -> TSBOperation {
+    TSBOperation {
+        operation_id: operation_id.to_string(),
+        token_id: token_id.to_string(),
+        r#type: 1,
+        from: from.to_string(),
+        to: to.to_string(),
+        amount: amount.to_string(),
+        timestamp: "1700000000".to_string(),
+        bitcoin_tx_id: String::new(),
+        torram_tx_id: String::new(),
+    }
+}
+
+/// An unspent output fixture.
+pub fn mock_utxo(tx_id: &str, vout: u32, amount: &str) -> TSBCTXO {
+    TSBCTXO {
+        tx_id: tx_id.to_string(),
+        vout,
+        amount: amount.to_string(),
+        used: false,
+    }
+}
+
+/// A page of a paginated TSB list.
+pub trait TsbPage: TsbResponse {
+    fn next_key(&self) -> Option<&str>;
+}
+
+impl TsbPage for GetAllTokensResponse {
+    fn next_key(&self) -> Option<&str> {
+        self.next_key.as_deref()
+    }
+}
+
+impl TsbPage for GetAllBalancesResponse {
+    fn next_key(&self) -> Option<&str> {
+        self.next_key.as_deref()
+    }
+}
+
+impl TsbPage for GetTokenOperationsResponse {
+    fn next_key(&self) -> Option<&str> {
+        self.next_key.as_deref()
+    }
+}
+
+impl TsbPage for GetUTXOsResponse {
+    fn next_key(&self) -> Option<&str> {
+        self.next_key.as_deref()
+    }
+}
+
+/// Reads every page of a TSB list in `MAX_LIMIT` steps, handing each to
+/// `visit`, the way a contract would walk the whole list. `page_query`
+/// builds the query for a `start_after` cursor and limit.
+pub fn for_each_page<T: TsbPage>(
+    deps: Deps<TSBQuery>,
+    page_query: impl Fn(Option<String>, Option<u32>) -> TSBQuery,
+    mut visit: impl FnMut(&T),
+) -> Result<(), ContractError> {
+    let mut start_after = None;
+    loop {
+        let page: T = query_tsb(deps, page_query(start_after, Some(MAX_LIMIT)))?;
+        visit(&page);
+        match page.next_key() {
+            Some(next_key) => start_after = Some(next_key.to_string()),
+            None => return Ok(()),
+        }
+    }
+}